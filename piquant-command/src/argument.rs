@@ -0,0 +1,468 @@
+use std::{error::Error, str::FromStr};
+
+use valence_protocol::{
+    block::{PropName, PropValue},
+    packets::s2c::commands::{Parser, StringArg},
+    types::GameMode,
+    BlockKind, BlockPos, BlockState, ItemKind, ItemStack, Uuid,
+};
+use vek::Vec3;
+
+use crate::{Arguments, Coordinate, Coordinates, EntitySelector};
+
+/// Where a command runs, positions typed relative to the sender resolve
+/// against it.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Origin {
+    pub position: [f64; 3],
+    pub yaw: f32,
+    pub pitch: f32,
+    /// How far the eyes of the sender are above its position, local
+    /// coordinates start there.
+    pub eye_height: f64,
+}
+
+/// A type `#[command]` functions can take as an argument.
+///
+/// Simple enums can derive it with `#[derive(FromArgument)]`, which makes the
+/// snake case names of their variants the only accepted words.
+pub trait FromArgument: Sized {
+    /// The name of the type in errors and `/help`.
+    const NAME: &'static str;
+    /// The number of words the argument is typed as, e.g. 3 for positions.
+    const WORDS: usize = 1;
+    /// The suggestion provider of the `CommandService` that completes the
+    /// argument, e.g. `blocks`.
+    const SUGGESTIONS: Option<&'static str> = None;
+
+    /// How clients parse the argument.
+    fn parser() -> Parser<'static>;
+
+    /// The words the argument is limited to, sent to clients as literals.
+    fn literals() -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Whether words other than the literals are accepted too, typed as
+    /// `parser`, e.g. a number of ticks besides `day` and `night`.
+    const OTHER_WORDS: bool = false;
+
+    /// Reads the argument at the cursor of `args`, which is never empty.
+    fn from_argument(args: &mut Arguments, origin: &Origin) -> Result<Self, Box<dyn Error>>;
+}
+
+/// Parses a single word with `parse`.
+fn parse_word<T>(
+    args: &mut Arguments,
+    parse: impl FnOnce(&str) -> Result<T, Box<dyn Error>>,
+) -> Result<T, Box<dyn Error>> {
+    parse(args.read_word())
+}
+
+/// Reads three coordinates. With `center`, absolute x and z typed without a
+/// decimal point are moved to the center of their block like in vanilla, so
+/// `5` is `5.5`.
+fn take_coordinates(args: &mut Arguments, center: bool) -> Result<Coordinates, Box<dyn Error>> {
+    let mut coordinates = [Coordinate::Absolute(0.0); 3];
+
+    for (axis, coordinate) in coordinates.iter_mut().enumerate() {
+        if args.is_empty() {
+            return Err("Incomplete position, expected 3 coordinates".into());
+        }
+
+        let word = args.read_word();
+        *coordinate = word.parse()?;
+
+        if let Coordinate::Absolute(v) = coordinate {
+            if center && axis != 1 && !word.contains('.') {
+                *v += 0.5;
+            }
+        }
+    }
+
+    Coordinates::new(coordinates)
+}
+
+/// Implements `FromArgument` for types that are a single word.
+macro_rules! word_arguments {
+    ($($ty:ty => $name:literal, $parser:expr, $parse:expr;)*) => {
+        $(
+            impl FromArgument for $ty {
+                const NAME: &'static str = $name;
+
+                fn parser() -> Parser<'static> {
+                    $parser
+                }
+
+                fn from_argument(args: &mut Arguments, _: &Origin) -> Result<Self, Box<dyn Error>> {
+                    parse_word(args, $parse)
+                }
+            }
+        )*
+    };
+}
+
+word_arguments! {
+    i32 => "Integer", Parser::Integer { min: None, max: None }, parse_number;
+    i64 => "Integer", Parser::Long { min: None, max: None }, parse_number;
+    f32 => "Float", Parser::Float { min: None, max: None }, parse_number;
+    f64 => "Float", Parser::Double { min: None, max: None }, parse_number;
+    bool => "Boolean", Parser::Bool, |word| {
+        word.parse().map_err(|_| format!("'{word}' is neither true nor false").into())
+    };
+    BlockState => "BlockState", Parser::BlockState, parse_block_state;
+    ItemStack => "ItemStack", Parser::ItemStack, parse_item_stack;
+    GameMode => "GameMode", Parser::GameMode, parse_game_mode;
+    Uuid => "Uuid", Parser::Uuid, |word| Ok(Uuid::parse_str(word)?);
+    Time => "Time", Parser::Time, str::parse;
+    EntitySelector => "EntitySelector", Parser::Entity { single: false, only_players: false }, EntitySelector::parse;
+}
+
+/// A number that `#[arg(min = .., max = ..)]` can limit.
+pub trait Bounded: FromArgument {
+    /// How clients parse the argument when it is limited to `min..=max`.
+    fn bounded_parser(min: Option<f64>, max: Option<f64>) -> Parser<'static>;
+
+    fn to_f64(&self) -> f64;
+
+    /// Checks that the argument is within `min..=max`.
+    fn check_bounds(&self, min: Option<f64>, max: Option<f64>) -> Result<(), String> {
+        let value = self.to_f64();
+
+        // `NaN` compares false against everything, so it would pass any limit.
+        if value.is_nan() && (min.is_some() || max.is_some()) {
+            return Err("must be a number, found NaN".to_string());
+        }
+
+        if let Some(min) = min.filter(|min| value < *min) {
+            return Err(format!("must not be less than {min}, found {value}"));
+        }
+
+        if let Some(max) = max.filter(|max| value > *max) {
+            return Err(format!("must not be more than {max}, found {value}"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Implements `Bounded` for numbers, `$variant` is their `Parser`.
+macro_rules! bounded_arguments {
+    ($($ty:ty => $variant:ident;)*) => {
+        $(
+            impl Bounded for $ty {
+                fn bounded_parser(min: Option<f64>, max: Option<f64>) -> Parser<'static> {
+                    Parser::$variant {
+                        min: min.map(|min| min as $ty),
+                        max: max.map(|max| max as $ty),
+                    }
+                }
+
+                fn to_f64(&self) -> f64 {
+                    *self as f64
+                }
+            }
+        )*
+    };
+}
+
+bounded_arguments! {
+    i32 => Integer;
+    i64 => Long;
+    f32 => Float;
+    f64 => Double;
+}
+
+fn parse_number<T: FromStr + Bounded>(word: &str) -> Result<T, Box<dyn Error>> {
+    // `NaN` and `inf` parse as floats, but no command means them.
+    word.parse()
+        .ok()
+        .filter(|number: &T| number.to_f64().is_finite())
+        .ok_or_else(|| format!("'{word}' is not a number").into())
+}
+
+/// A string that is a single word or in quotes, e.g. `"hello world"`.
+impl FromArgument for String {
+    const NAME: &'static str = "String";
+
+    fn parser() -> Parser<'static> {
+        Parser::String(StringArg::QuotablePhrase)
+    }
+
+    fn from_argument(args: &mut Arguments, _: &Origin) -> Result<Self, Box<dyn Error>> {
+        Ok(args.read_quotable()?)
+    }
+}
+
+/// A string argument of a single word, quotes are part of the word.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Word(pub String);
+
+impl AsRef<str> for Word {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromArgument for Word {
+    const NAME: &'static str = "Word";
+
+    fn parser() -> Parser<'static> {
+        Parser::String(StringArg::SingleWord)
+    }
+
+    fn from_argument(args: &mut Arguments, _: &Origin) -> Result<Self, Box<dyn Error>> {
+        Ok(Word(args.read_word().to_string()))
+    }
+}
+
+/// A string argument that takes the rest of the command, like the message of
+/// `/say`. It has to be the last argument.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Greedy(pub String);
+
+impl AsRef<str> for Greedy {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromArgument for Greedy {
+    const NAME: &'static str = "Text";
+    const WORDS: usize = usize::MAX;
+
+    fn parser() -> Parser<'static> {
+        Parser::String(StringArg::GreedyPhrase)
+    }
+
+    fn from_argument(args: &mut Arguments, _: &Origin) -> Result<Self, Box<dyn Error>> {
+        Ok(Greedy(args.read_greedy().to_string()))
+    }
+}
+
+/// A duration in ticks, typed as `20`, `20t`, `1s` or `1d`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Time(pub i64);
+
+impl FromStr for Time {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (value, unit) = match s.char_indices().last() {
+            Some((i, 't')) => (&s[..i], 1.0),
+            Some((i, 's')) => (&s[..i], 20.0),
+            Some((i, 'd')) => (&s[..i], 24000.0),
+            _ => (s, 1.0),
+        };
+
+        let value: f64 = parse_number(value)?;
+        if value < 0.0 {
+            return Err("Tick count must be non-negative".into());
+        }
+
+        // Vanilla limits times to an integer of ticks.
+        let ticks = (value * unit).round();
+        if ticks > i32::MAX as f64 {
+            return Err(format!("Tick count must not be more than {}", i32::MAX).into());
+        }
+
+        Ok(Time(ticks as i64))
+    }
+}
+
+fn parse_block_state(s: &str) -> Result<BlockState, Box<dyn Error>> {
+    let (name, props) = match s.split_once('[') {
+        Some((name, props)) => match props.strip_suffix(']') {
+            Some(props) => (name, props),
+            None => return Err(format!("Expected ] to close the block properties of {s}").into()),
+        },
+        None => (s, ""),
+    };
+
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    let kind = BlockKind::from_str(name).ok_or_else(|| format!("Unknown block type '{name}'"))?;
+
+    let mut state = BlockState::from_kind(kind);
+
+    for prop in props.split(',').filter(|prop| !prop.trim().is_empty()) {
+        let (key, value) = prop.split_once('=').ok_or_else(|| {
+            format!(
+                "Expected value for property '{}' on block {name}",
+                prop.trim()
+            )
+        })?;
+        let (key, value) = (key.trim(), value.trim());
+
+        let prop_name = PropName::from_str(key)
+            .filter(|prop_name| kind.props().contains(prop_name))
+            .ok_or_else(|| format!("Block {name} does not have property '{key}'"))?;
+        let prop_value = PropValue::from_str(value)
+            .filter(|prop_value| {
+                state.set(prop_name, *prop_value).get(prop_name) == Some(*prop_value)
+            })
+            .ok_or_else(|| format!("Block {name} does not accept '{value}' for {key} property"))?;
+
+        state = state.set(prop_name, prop_value);
+    }
+
+    Ok(state)
+}
+
+fn parse_item_stack(s: &str) -> Result<ItemStack, Box<dyn Error>> {
+    if s.contains('{') {
+        return Err("Item NBT is not supported".into());
+    }
+
+    let name = s.strip_prefix("minecraft:").unwrap_or(s);
+    let kind = ItemKind::from_str(name).ok_or_else(|| format!("Unknown item '{name}'"))?;
+
+    Ok(ItemStack::new(kind, 1, None))
+}
+
+fn parse_game_mode(s: &str) -> Result<GameMode, Box<dyn Error>> {
+    match s {
+        "survival" => Ok(GameMode::Survival),
+        "creative" => Ok(GameMode::Creative),
+        "adventure" => Ok(GameMode::Adventure),
+        "spectator" => Ok(GameMode::Spectator),
+        _ => Err(format!("Unknown game mode: {s}").into()),
+    }
+}
+
+impl FromArgument for BlockKind {
+    const NAME: &'static str = "Block";
+    const SUGGESTIONS: Option<&'static str> = Some("blocks");
+
+    fn parser() -> Parser<'static> {
+        Parser::ResourceLocation
+    }
+
+    fn from_argument(args: &mut Arguments, _: &Origin) -> Result<Self, Box<dyn Error>> {
+        let name = args.read_word();
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+
+        Ok(BlockKind::from_str(name).ok_or_else(|| format!("Unknown block type '{name}'"))?)
+    }
+}
+
+impl FromArgument for ItemKind {
+    const NAME: &'static str = "Item";
+    const SUGGESTIONS: Option<&'static str> = Some("items");
+
+    fn parser() -> Parser<'static> {
+        Parser::ResourceLocation
+    }
+
+    fn from_argument(args: &mut Arguments, _: &Origin) -> Result<Self, Box<dyn Error>> {
+        let name = args.read_word();
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+
+        Ok(ItemKind::from_str(name).ok_or_else(|| format!("Unknown item '{name}'"))?)
+    }
+}
+
+impl FromArgument for BlockPos {
+    const NAME: &'static str = "BlockPos";
+    const WORDS: usize = 3;
+
+    fn parser() -> Parser<'static> {
+        Parser::BlockPos
+    }
+
+    fn from_argument(args: &mut Arguments, origin: &Origin) -> Result<Self, Box<dyn Error>> {
+        let coordinates = take_coordinates(args, false)?;
+        Ok(coordinates.block_pos(origin))
+    }
+}
+
+impl FromArgument for Vec3<f64> {
+    const NAME: &'static str = "Vec3";
+    const WORDS: usize = 3;
+
+    fn parser() -> Parser<'static> {
+        Parser::Vec3
+    }
+
+    fn from_argument(args: &mut Arguments, origin: &Origin) -> Result<Self, Box<dyn Error>> {
+        let coordinates = take_coordinates(args, true)?;
+        Ok(coordinates.resolve(origin).into())
+    }
+}
+
+/// A yaw and pitch in degrees, either may be relative to the sender like
+/// `~90 ~`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Rotation {
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl FromArgument for Rotation {
+    const NAME: &'static str = "Rotation";
+    const WORDS: usize = 2;
+
+    fn parser() -> Parser<'static> {
+        Parser::Rotation
+    }
+
+    fn from_argument(args: &mut Arguments, origin: &Origin) -> Result<Self, Box<dyn Error>> {
+        let mut angles = [origin.yaw, origin.pitch];
+
+        for angle in &mut angles {
+            if args.is_empty() {
+                return Err("Incomplete rotation, expected yaw and pitch".into());
+            }
+
+            *angle = match args.read_word().parse()? {
+                Coordinate::Absolute(value) => value as f32,
+                Coordinate::Relative(offset) => *angle + offset as f32,
+                Coordinate::Local(_) => {
+                    return Err("Local coordinates can't be used for rotations".into())
+                }
+            };
+        }
+
+        let [yaw, pitch] = angles;
+
+        Ok(Self {
+            yaw: (yaw + 180.0).rem_euclid(360.0) - 180.0,
+            pitch: pitch.clamp(-90.0, 90.0),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vec3(input: &str, origin: &Origin) -> Vec3<f64> {
+        let mut args = Arguments::new(input, 0);
+        Vec3::from_argument(&mut args, origin).unwrap()
+    }
+
+    #[test]
+    fn whole_x_and_z_are_centered() {
+        let origin = Origin::default();
+
+        assert_eq!(vec3("5 64 -3", &origin), Vec3::new(5.5, 64.0, -2.5));
+        assert_eq!(vec3("5.0 64 -3.25", &origin), Vec3::new(5.0, 64.0, -3.25));
+    }
+
+    #[test]
+    fn relative_is_not_centered() {
+        let origin = Origin {
+            position: [1.0, 2.0, 3.0],
+            ..Default::default()
+        };
+
+        assert_eq!(vec3("~ ~ ~1", &origin), Vec3::new(1.0, 2.0, 4.0));
+    }
+
+    #[test]
+    fn block_pos_is_not_centered() {
+        let mut args = Arguments::new("5 64 -3", 0);
+        let pos = BlockPos::from_argument(&mut args, &Origin::default()).unwrap();
+
+        assert_eq!(pos, BlockPos::new(5, 64, -3));
+    }
+}
@@ -1,31 +1,316 @@
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap};
 
-use valence_protocol::packets::s2c::commands::Node;
+use valence_protocol::{packets::s2c::commands::Node, text::Text, VarInt};
 
-use crate::{command_stack::CommandStack, parse, Command, CommandDef};
+use crate::{
+    command_stack::CommandStack, help_command, help_page, parse, ArgumentDef, Arguments, Command,
+    CommandDef, CommandError, CommandSuggestionsResponse, DuplicateCommand, PermissionCheck,
+    Registration, SuggestionContext, SuggestionMatch, SuggestionProvider,
+};
 
 pub struct CommandService<G, C, W> {
+    /// Commands by name, subcommands are named after their group as well,
+    /// e.g. "time set".
     commands: HashMap<String, Command<G, C, W>>,
-    command_stack: CommandStack,
+    /// The subcommand names of each command group.
+    groups: HashMap<String, Vec<&'static str>>,
+    /// The definitions of all commands, to find the argument being completed.
+    defs: HashMap<String, CommandDef>,
+    /// Suggestion providers by the name arguments refer to them with.
+    providers: HashMap<&'static str, SuggestionProvider<G, C, W>>,
+    /// The names of commands by their aliases.
+    aliases: HashMap<String, String>,
+    /// Commands that stand for other command lines, see `add_expansion`.
+    expansions: HashMap<String, String>,
+    /// Counts the changes to the commands, see `version`.
+    version: u64,
+    /// The changes since `take_changes` was last called.
+    changes: Vec<CommandChange>,
+}
+
+/// A command that was added or removed, see `CommandService::take_changes`.
+#[derive(Clone, Debug)]
+pub struct CommandChange {
+    pub name: String,
+    pub permission: Option<&'static str>,
+    pub op_level: i32,
+}
+
+impl CommandChange {
+    fn new(def: &CommandDef) -> Self {
+        Self {
+            name: def.name.to_string(),
+            permission: def.permission,
+            op_level: def.op_level,
+        }
+    }
+
+    /// Returns `true` if `client` could see the command, so its command graph
+    /// changed.
+    pub fn affects<G, C>(&self, game: &G, client: &C) -> bool
+    where
+        G: PermissionCheck<C>,
+    {
+        (self.permission.is_none() && self.op_level == 0)
+            || game.has_permission(client, self.permission, self.op_level)
+    }
 }
 
 impl<G, C, W> CommandService<G, C, W> {
     pub fn new() -> Self {
         Self {
             commands: HashMap::new(),
-            command_stack: CommandStack::new(),
+            groups: HashMap::new(),
+            defs: HashMap::new(),
+            providers: HashMap::from([
+                ("blocks", SuggestionProvider::BlockKinds),
+                ("items", SuggestionProvider::ItemKinds),
+                ("players", SuggestionProvider::PlayerNames),
+                ("worlds", SuggestionProvider::WorldNames),
+            ]),
+            aliases: HashMap::new(),
+            expansions: HashMap::new(),
+            version: 0,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Goes up whenever a command is added or removed, so a command graph
+    /// built at an older version is out of date.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the commands added or removed since the last call, to send new
+    /// command graphs to the clients they affect.
+    pub fn take_changes(&mut self) -> Vec<CommandChange> {
+        std::mem::take(&mut self.changes)
+    }
+
+    fn changed(&mut self, change: CommandChange) {
+        self.version += 1;
+        self.changes.push(change);
+    }
+
+    /// A service with every `#[command]` in the program that was written for
+    /// these types.
+    pub fn with_registered() -> Result<Self, DuplicateCommand>
+    where
+        G: 'static,
+        C: 'static,
+        W: 'static,
+    {
+        let mut service = Self::new();
+
+        for registration in inventory::iter::<Registration> {
+            (registration.register)(&mut service).transpose()?;
+        }
+
+        Ok(service)
+    }
+
+    /// Adds a provider arguments can use with `#[suggest("name")]`.
+    pub fn add_suggestions(&mut self, name: &'static str, provider: SuggestionProvider<G, C, W>) {
+        self.providers.insert(name, provider);
+    }
+
+    pub fn add_command(
+        &mut self,
+        command_def: CommandDef,
+        command: Command<G, C, W>,
+    ) -> Result<(), DuplicateCommand> {
+        let name = command_def.name.to_string();
+
+        self.add_aliases(&command_def)?;
+        self.changed(CommandChange::new(&command_def));
+        self.commands.insert(name.clone(), command);
+        self.defs.insert(name, command_def);
+
+        Ok(())
+    }
+
+    /// Removes a command or command group along with its aliases.
+    pub fn remove_command(&mut self, name: &str) -> Option<CommandDef> {
+        let name = self.resolve(name).to_string();
+        let def = self.defs.remove(&name)?;
+
+        self.commands.remove(&name);
+        for sub in self.groups.remove(&name).unwrap_or_default() {
+            self.commands.remove(&format!("{name} {sub}"));
+        }
+        self.aliases.retain(|_, command| *command != name);
+
+        self.changed(CommandChange::new(&def));
+
+        Some(def)
+    }
+
+    /// Claims the name and aliases of a command, failing if another command
+    /// has any of them.
+    fn add_aliases(&mut self, command_def: &CommandDef) -> Result<(), DuplicateCommand> {
+        for name in std::iter::once(&command_def.name).chain(&command_def.aliases) {
+            if self.defs.contains_key(*name)
+                || self.aliases.contains_key(*name)
+                || self.expansions.contains_key(*name)
+            {
+                return Err(DuplicateCommand(name.to_string()));
+            }
+        }
+
+        for alias in &command_def.aliases {
+            self.aliases
+                .insert(alias.to_string(), command_def.name.to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Adds a command that runs `command_line` with the words typed after
+    /// `name` appended, e.g. `day` for `time set day`. Fails if a command,
+    /// alias or other expansion already has the name.
+    pub fn add_expansion(
+        &mut self,
+        name: impl Into<String>,
+        command_line: impl Into<String>,
+    ) -> Result<(), DuplicateCommand> {
+        let name = name.into();
+
+        if self.defs.contains_key(&name)
+            || self.aliases.contains_key(&name)
+            || self.expansions.contains_key(&name)
+        {
+            return Err(DuplicateCommand(name));
+        }
+
+        self.changed(CommandChange {
+            name: name.clone(),
+            permission: None,
+            op_level: 0,
+        });
+        self.expansions.insert(name, command_line.into());
+
+        Ok(())
+    }
+
+    /// Removes an expansion, returning its command line.
+    pub fn remove_expansion(&mut self, name: &str) -> Option<String> {
+        let (name, command_line) = self.expansions.remove_entry(name)?;
+        self.changed(CommandChange {
+            name,
+            permission: None,
+            op_level: 0,
+        });
+
+        Some(command_line)
+    }
+
+    /// Replaces an expansion at the start of `input` with its command line.
+    pub fn expand<'a>(&self, input: &'a str) -> Cow<'a, str> {
+        let input = input.trim_start();
+        let (name, rest) = input.split_once(' ').unwrap_or((input, ""));
+
+        match self.expansions.get(name.trim_start_matches('/')) {
+            Some(command_line) if rest.trim().is_empty() => command_line.clone().into(),
+            Some(command_line) => format!("{command_line} {}", rest.trim()).into(),
+            None => input.into(),
         }
     }
 
-    pub fn add_command(&mut self, command_def: CommandDef, command: Command<G, C, W>) {
+    /// The name of the command `name` refers to, resolving aliases.
+    fn resolve<'a>(&'a self, name: &'a str) -> &'a str {
+        let name = name.trim_start_matches('/');
+        self.aliases.get(name).map_or(name, String::as_str)
+    }
+
+    /// Adds a command group, see `#[command]` on modules.
+    pub fn add_command_group(
+        &mut self,
+        command_def: CommandDef,
+        subcommands: Vec<(&'static str, Command<G, C, W>)>,
+    ) -> Result<(), DuplicateCommand> {
         let name = command_def.name.to_string();
 
-        self.commands.insert(name, command);
-        self.command_stack.register(command_def);
+        self.add_aliases(&command_def)?;
+        self.changed(CommandChange::new(&command_def));
+        let names = subcommands.iter().map(|(sub, _)| *sub).collect();
+        self.groups.insert(name.clone(), names);
+
+        for (sub, command) in subcommands {
+            self.commands.insert(format!("{name} {sub}"), command);
+        }
+
+        self.defs.insert(name, command_def);
+
+        Ok(())
+    }
+
+    /// Returns the command graph with only the commands `client` may run.
+    pub fn get_command_defs(&self, game: &G, client: &C) -> (i32, Vec<Node<'_>>)
+    where
+        G: PermissionCheck<C>,
+    {
+        let mut command_stack = CommandStack::new();
+
+        let mut names: Vec<_> = self.defs.keys().collect();
+        names.sort();
+
+        for name in names {
+            let def = &self.defs[name];
+
+            if Self::may_run(def, game, client) {
+                command_stack.register(def);
+            }
+        }
+
+        let mut expansions: Vec<_> = self.expansions.keys().collect();
+        expansions.sort();
+
+        for name in expansions {
+            let registered = command_stack.register_expansion(name);
+            debug_assert!(
+                registered.is_ok(),
+                "add_expansion refuses names of commands"
+            );
+        }
+
+        command_stack.list()
     }
 
-    pub fn get_command_defs(&self) -> (i32, Vec<Node>) {
-        self.command_stack.list().clone()
+    /// Renders `/help`: a page of the commands `client` may run for no or a
+    /// numeric `query`, otherwise the help of the named command.
+    pub fn help(&self, game: &G, client: &C, query: Option<&str>) -> Result<Vec<Text>, CommandError>
+    where
+        G: PermissionCheck<C>,
+    {
+        let mut defs: Vec<_> = self
+            .defs
+            .values()
+            .filter(|def| Self::may_run(def, game, client))
+            .collect();
+        defs.sort_by_key(|def| def.name);
+
+        let Some(query) = query else {
+            return help_page(&defs, 1);
+        };
+
+        if let Ok(page) = query.parse() {
+            return help_page(&defs, page);
+        }
+
+        let name = self.resolve(query);
+        match defs.into_iter().find(|def| def.name == name) {
+            Some(def) => Ok(help_command(def)),
+            None => Err(CommandError::Execution(
+                format!("Unknown command: {name}").into(),
+            )),
+        }
+    }
+
+    fn may_run(def: &CommandDef, game: &G, client: &C) -> bool
+    where
+        G: PermissionCheck<C>,
+    {
+        def.is_public() || game.has_permission(client, def.permission, def.op_level)
     }
 
     pub fn execute(
@@ -34,20 +319,276 @@ impl<G, C, W> CommandService<G, C, W> {
         game: &G,
         client: &mut C,
         world: &mut W,
+    ) -> Result<(), CommandError>
+    where
+        G: PermissionCheck<C>,
+    {
+        let (command, args) = self.lookup(input, game, client)?;
+        Self::run(command, args, game, client, world)
+    }
+
+    /// Parses `input` and finds the command it runs, checking that `client`
+    /// may run it. Unlike `execute`, this lets the caller release the
+    /// service before running the command, so commands can change it.
+    pub fn lookup(
+        &self,
+        input: &str,
+        game: &G,
+        client: &C,
+    ) -> Result<(Command<G, C, W>, Arguments), CommandError>
+    where
+        G: PermissionCheck<C>,
+    {
+        let (cmd, mut args) = parse(input)?;
+
+        let mut cmd = self.resolve(&cmd.0).to_string();
+
+        let Some(def) = self.defs.get(&cmd) else {
+            return Err(CommandError::UnknownCommand { span: args.span() });
+        };
+
+        if !Self::may_run(def, game, client) {
+            return Err(CommandError::PermissionDenied);
+        }
+
+        if let Some(subcommands) = self.groups.get(&cmd) {
+            let sub = args.read_word();
+            if !subcommands.contains(&sub) {
+                return Err(CommandError::UnknownCommand { span: args.span() });
+            }
+
+            cmd = format!("{cmd} {sub}");
+        }
+
+        let command = self
+            .commands
+            .get(&cmd)
+            .ok_or(CommandError::UnknownCommand { span: args.span() })?;
+
+        Ok((*command, args))
+    }
+
+    /// Runs a command found with `lookup`.
+    pub fn run(
+        command: Command<G, C, W>,
+        args: Arguments,
+        game: &G,
+        client: &mut C,
+        world: &mut W,
+    ) -> Result<(), CommandError> {
+        command(args, game, client, world).map_err(|e| match e.downcast::<CommandError>() {
+            Ok(e) => *e,
+            Err(e) => CommandError::Execution(e),
+        })
+    }
+
+    /// Runs the first of `overloads` that its arguments parse for. If they
+    /// parse for none, the error that got the furthest into the input is
+    /// returned. `#[command]` modules with `#[overload]` functions run with
+    /// this.
+    pub fn run_overloads(
+        overloads: &[Command<G, C, W>],
+        args: Arguments,
+        game: &G,
+        client: &mut C,
+        world: &mut W,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let (cmd, args) = parse(input)?;
+        let mut furthest: Option<CommandError> = None;
+
+        for overload in overloads {
+            let e = match overload(args.clone(), game, client, world) {
+                Err(e) => e.downcast::<CommandError>()?,
+                result => return result,
+            };
+
+            // Only errors in the input mean the arguments are for another
+            // overload.
+            let Some(span) = e.span() else {
+                return Err(e);
+            };
+
+            if furthest
+                .as_ref()
+                .and_then(CommandError::span)
+                .is_none_or(|furthest| span.start > furthest.start)
+            {
+                furthest = Some(*e);
+            }
+        }
+
+        Err(furthest
+            .unwrap_or(CommandError::UnknownCommand { span: args.span() })
+            .into())
+    }
+
+    /// Answers a suggestion request for `text`, the command typed so far.
+    /// Nothing is suggested for commands the client may not run.
+    pub fn suggest(
+        &self,
+        transaction_id: i32,
+        text: &str,
+        context: &SuggestionContext<G, C, W>,
+    ) -> CommandSuggestionsResponse
+    where
+        G: PermissionCheck<C>,
+    {
+        let mut words = split_words(text);
+        let (start, current) = match text.ends_with(' ') {
+            true => (text.len(), ""),
+            false => words.pop().unwrap_or((0, "")),
+        };
+
+        let permitted = words
+            .first()
+            .and_then(|(_, name)| self.defs.get(self.resolve(name)))
+            .is_some_and(|def| Self::may_run(def, context.game, context.client));
+
+        let matches = self
+            .arguments_at(&words)
+            .into_iter()
+            .filter(|_| permitted)
+            .find_map(|arg| arg.suggestions)
+            .and_then(|name| self.providers.get(name))
+            .map(|provider| provider.suggest(context, current))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|suggestion| SuggestionMatch {
+                suggestion: suggestion.text,
+                tooltip: suggestion.tooltip.map(Into::into),
+            })
+            .collect();
+
+        CommandSuggestionsResponse {
+            transaction_id: VarInt(transaction_id),
+            start: VarInt(start as i32),
+            length: VarInt(current.len() as i32),
+            matches,
+        }
+    }
+
+    /// Completes the command names, subcommands and literal arguments of
+    /// `text`, returning where the completed word starts.
+    pub fn complete(&self, text: &str) -> (usize, Vec<String>) {
+        let mut words = split_words(text);
+        let (start, current) = match text.ends_with(' ') {
+            true => (text.len(), ""),
+            false => words.pop().unwrap_or((0, "")),
+        };
+
+        let group = match &words[..] {
+            [(_, name)] => self.groups.get(self.resolve(name)),
+            _ => None,
+        };
+
+        let candidates: Vec<&str> = match (words.is_empty(), group) {
+            (true, _) => self
+                .defs
+                .keys()
+                .chain(self.aliases.keys())
+                .chain(self.expansions.keys())
+                .map(String::as_str)
+                .collect(),
+            (false, Some(subcommands)) => subcommands.clone(),
+            (false, None) => self
+                .arguments_at(&words)
+                .into_iter()
+                .flat_map(|arg| arg.literals.iter().copied())
+                .collect(),
+        };
+
+        let (start, current) = match (words.is_empty(), current.strip_prefix('/')) {
+            (true, Some(current)) => (start + 1, current),
+            _ => (start, current),
+        };
+
+        let mut candidates: Vec<String> = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(current))
+            .map(str::to_string)
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+
+        (start, candidates)
+    }
 
-        let mut cmd = cmd.0;
-        if cmd.starts_with('/') {
-            cmd.remove(0);
+    /// Returns the arguments that could follow the complete `words`, one for
+    /// each overload of the command.
+    fn arguments_at(&self, words: &[(usize, &str)]) -> Vec<&ArgumentDef> {
+        let Some(((_, name), mut words)) = words.split_first() else {
+            return Vec::new();
+        };
+        let Some(mut def) = self.defs.get(self.resolve(name)) else {
+            return Vec::new();
+        };
+
+        if !def.subcommands.is_empty() {
+            let Some(((_, sub), rest)) = words.split_first() else {
+                return Vec::new();
+            };
+            let Some(sub) = def.subcommands.iter().find(|def| def.name == *sub) else {
+                return Vec::new();
+            };
+
+            def = sub;
+            words = rest;
         }
 
-        // args.push_back(Argument::Client(client));
+        let overloads = match def.overloads.is_empty() {
+            true => std::slice::from_ref(def),
+            false => &def.overloads[..],
+        };
+
+        overloads
+            .iter()
+            .filter_map(|overload| argument_at(&overload.arguments, words.len()))
+            .collect()
+    }
+}
 
-        if let Some(command) = self.commands.get(&cmd) {
-            command(args, game, client, world)
-        } else {
-            Err("Command not found".into())
+/// The argument after `words` complete words.
+fn argument_at(args: &[ArgumentDef], words: usize) -> Option<&ArgumentDef> {
+    let mut consumed = 0usize;
+
+    for arg in args {
+        consumed = consumed.saturating_add(arg.words);
+
+        if consumed > words {
+            return Some(arg);
         }
     }
+
+    None
+}
+
+/// Splits a command into words and where they start. Quoted strings and the
+/// options of selectors are single words, like in `Arguments`.
+fn split_words(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut in_options = false;
+    let mut in_quotes = false;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            ' ' if !in_options && !in_quotes => {
+                if let Some(start) = start.take() {
+                    words.push((start, &text[start..i]));
+                }
+                continue;
+            }
+            '"' => in_quotes = !in_quotes,
+            '[' if !in_quotes => in_options = true,
+            ']' if !in_quotes => in_options = false,
+            _ => {}
+        }
+
+        start.get_or_insert(i);
+    }
+
+    if let Some(start) = start {
+        words.push((start, &text[start..]));
+    }
+
+    words
 }
@@ -1,90 +1,296 @@
+use std::{
+    collections::HashMap,
+    mem::{self, Discriminant},
+};
+
 use valence_protocol::{
-    packets::s2c::commands::{Node, NodeData, Parser, StringArg},
+    packets::s2c::commands::{Node, NodeData, Parser, StringArg, Suggestion},
     VarInt,
 };
 
-use crate::CommandDef;
+use crate::{ArgumentDef, CommandDef, DuplicateCommand};
 
-pub struct CommandStack {
-    commands: Vec<Node<'static>>,
-    command_ids: Vec<i32>,
+/// The command graph sent to clients. Every argument of a command is a child
+/// of the argument before it, and a node is only executable if all arguments
+/// after it are optional.
+pub struct CommandStack<'a> {
+    /// The tree of registered commands, the root is always the first node.
+    commands: Vec<Node<'a>>,
 }
 
-impl CommandStack {
+impl<'a> CommandStack<'a> {
     pub fn new() -> Self {
         Self {
-            commands: Vec::new(),
-            command_ids: Vec::new(),
+            commands: vec![Node {
+                children: Vec::new(),
+                data: NodeData::Root,
+                executable: false,
+                redirect_node: None,
+            }],
         }
     }
 
-    fn add(&mut self, node: Node<'static>) -> i32 {
-        self.commands.push(node);
-        self.commands.len() as i32 - 1
+    /// Returns the child of `parent` with the same name and parser as `data`,
+    /// adding it if there is none.
+    fn child(&mut self, parent: usize, data: NodeData<'a>) -> usize {
+        let existing = self.commands[parent]
+            .children
+            .iter()
+            .map(|id| id.0 as usize)
+            .find(|id| same_node(&self.commands[*id].data, &data));
+
+        if let Some(id) = existing {
+            return id;
+        }
+
+        self.commands.push(Node {
+            children: Vec::new(),
+            data,
+            executable: false,
+            redirect_node: None,
+        });
+
+        let id = self.commands.len() - 1;
+        self.commands[parent].children.push(VarInt(id as i32));
+
+        id
     }
 
-    pub fn register(&mut self, command_def: CommandDef) {
-        let name = command_def.name;
-        let args = command_def.arguments;
-
-        let mut arg_ids = Vec::new();
-        // loop over args
-        for arg in args {
-            let parser = match arg.ty {
-                "i64" => Parser::Integer {
-                    min: None,
-                    max: None,
-                },
-                "f64" => Parser::Float {
-                    min: None,
-                    max: None,
-                },
-                "bool" => Parser::Bool,
-                "string" => Parser::String(StringArg::QuotablePhrase),
-                _ => Parser::String(StringArg::QuotablePhrase),
-            };
+    pub fn register(&mut self, command_def: &CommandDef) {
+        let literal = self.register_at(0, command_def);
 
-            let a = Node {
-                children: Vec::new(),
-                data: NodeData::Argument {
-                    name: &arg.name[..],
-                    parser,
-                    suggestion: None,
-                },
-                executable: true,
-                redirect_node: None,
-            };
+        // Aliases parse like the command they redirect to.
+        for alias in &command_def.aliases {
+            let node = self.child(0, NodeData::Literal { name: alias });
+            self.commands[node].executable = self.commands[literal].executable;
+            self.commands[node].redirect_node = Some(VarInt(literal as i32));
+        }
+    }
 
-            let arg_id = self.add(a);
-            arg_ids.push(arg_id);
+    /// Registers a command that expands to another command line, extra words
+    /// are passed on as is. Fails if a command already has the name.
+    pub fn register_expansion(&mut self, name: &'a str) -> Result<(), DuplicateCommand> {
+        let taken = self.commands[0].children.iter().any(|id| {
+            matches!(self.commands[id.0 as usize].data, NodeData::Literal { name: other } if other == name)
+        });
+
+        if taken {
+            return Err(DuplicateCommand(name.to_string()));
         }
 
-        let children = arg_ids.iter().map(|id| VarInt(*id)).collect();
+        let literal = self.child(0, NodeData::Literal { name });
+        self.commands[literal].executable = true;
 
-        let command = Node {
-            children,
-            data: NodeData::Literal { name },
-            executable: true,
-            redirect_node: None,
+        let arguments = self.child(
+            literal,
+            NodeData::Argument {
+                name: "arguments",
+                parser: Parser::String(StringArg::GreedyPhrase),
+                suggestion: None,
+            },
+        );
+        self.commands[arguments].executable = true;
+
+        Ok(())
+    }
+
+    fn register_at(&mut self, parent: usize, command_def: &CommandDef) -> usize {
+        let literal = self.child(
+            parent,
+            NodeData::Literal {
+                name: command_def.name,
+            },
+        );
+
+        if command_def.subcommands.is_empty() && command_def.overloads.is_empty() {
+            self.register_arguments(literal, &command_def.arguments);
+        }
+
+        for overload in &command_def.overloads {
+            self.register_arguments(literal, &overload.arguments);
+        }
+
+        for subcommand in &command_def.subcommands {
+            self.register_at(literal, subcommand);
+        }
+
+        literal
+    }
+
+    /// Chains `args` below `parent`. Arguments with literals branch into one
+    /// literal node per word, next to the argument node if they accept other
+    /// words too.
+    fn register_arguments(&mut self, parent: usize, args: &[ArgumentDef]) {
+        if args.iter().all(|arg| arg.optional) {
+            self.commands[parent].executable = true;
+        }
+
+        let Some((arg, rest)) = args.split_first() else {
+            return;
         };
 
-        let id = self.add(command);
+        if arg.literals.is_empty() || arg.other_words {
+            let node = self.child(
+                parent,
+                NodeData::Argument {
+                    name: arg.name,
+                    parser: arg.parser.clone(),
+                    suggestion: arg.suggestions.map(|_| Suggestion::AskServer),
+                },
+            );
+
+            self.register_arguments(node, rest);
+        }
 
-        self.command_ids.push(id);
+        for literal in &arg.literals {
+            let node = self.child(parent, NodeData::Literal { name: literal });
+            self.register_arguments(node, rest);
+        }
     }
 
-    pub fn list(&self) -> (i32, Vec<Node>) {
-        let mut cloned_commands = self.commands.clone();
+    /// Returns the index of the root node and the graph with identical
+    /// subtrees merged into one node.
+    pub fn list(&self) -> (i32, Vec<Node<'a>>) {
+        let mut nodes = Vec::new();
+        let mut interned = HashMap::new();
 
-        let root_node = Node {
-            children: self.command_ids.iter().map(|id| VarInt(*id)).collect(),
-            data: NodeData::Root,
-            executable: false,
-            redirect_node: None,
+        let root = intern(&self.commands, 0, &mut nodes, &mut interned);
+
+        (root, nodes)
+    }
+}
+
+/// Nodes are the same branch when a client would parse them the same way.
+fn same_node(a: &NodeData, b: &NodeData) -> bool {
+    match (a, b) {
+        (NodeData::Literal { name: a }, NodeData::Literal { name: b }) => a == b,
+        (
+            NodeData::Argument {
+                name: a,
+                parser: parser_a,
+                ..
+            },
+            NodeData::Argument {
+                name: b,
+                parser: parser_b,
+                ..
+            },
+        ) => a == b && ParserKey::new(parser_a) == ParserKey::new(parser_b),
+        _ => false,
+    }
+}
+
+/// What tells parsers apart: their type and their properties, with bounds
+/// compared by their bits.
+#[derive(PartialEq, Eq, Hash)]
+struct ParserKey<'a> {
+    kind: Discriminant<Parser<'a>>,
+    props: [Option<u64>; 2],
+    registry: Option<&'a str>,
+}
+
+impl<'a> ParserKey<'a> {
+    fn new(parser: &Parser<'a>) -> Self {
+        let props = match *parser {
+            Parser::Float { min, max } => [
+                min.map(|min| min.to_bits() as u64),
+                max.map(|max| max.to_bits() as u64),
+            ],
+            Parser::Double { min, max } => [min.map(f64::to_bits), max.map(f64::to_bits)],
+            Parser::Integer { min, max } => [min.map(|min| min as u64), max.map(|max| max as u64)],
+            Parser::Long { min, max } => [min.map(|min| min as u64), max.map(|max| max as u64)],
+            Parser::String(arg) => [Some(arg as u64), None],
+            Parser::Entity {
+                single,
+                only_players,
+            } => [Some(single as u64), Some(only_players as u64)],
+            Parser::ScoreHolder { allow_multiple } => [Some(allow_multiple as u64), None],
+            _ => [None, None],
         };
 
-        cloned_commands.push(root_node);
+        let registry = match *parser {
+            Parser::ResourceOrTag { registry }
+            | Parser::ResourceOrTagKey { registry }
+            | Parser::Resource { registry }
+            | Parser::ResourceKey { registry } => Some(registry.into_inner()),
+            _ => None,
+        };
 
-        (cloned_commands.len() as i32 - 1, cloned_commands)
+        Self {
+            kind: mem::discriminant(parser),
+            props,
+            registry,
+        }
     }
 }
+
+/// Everything a node is sent with, its children and redirect as indices into
+/// the interned nodes.
+#[derive(PartialEq, Eq, Hash)]
+struct NodeKey<'a> {
+    literal: Option<&'a str>,
+    argument: Option<(&'a str, ParserKey<'a>, Option<u8>)>,
+    executable: bool,
+    redirect_node: Option<i32>,
+    children: Vec<i32>,
+}
+
+/// Copies the subtree at `id` into `nodes` bottom up, reusing nodes that were
+/// already added with the same data and children.
+fn intern<'a>(
+    tree: &[Node<'a>],
+    id: usize,
+    nodes: &mut Vec<Node<'a>>,
+    interned: &mut HashMap<NodeKey<'a>, i32>,
+) -> i32 {
+    let node = &tree[id];
+
+    let mut children: Vec<_> = node
+        .children
+        .iter()
+        .map(|child| VarInt(intern(tree, child.0 as usize, nodes, interned)))
+        .collect();
+    children.sort_by_key(|child| child.0);
+    children.dedup_by_key(|child| child.0);
+
+    // Redirects point into `tree` and have to point into `nodes` instead.
+    let redirect_node = node
+        .redirect_node
+        .map(|target| VarInt(intern(tree, target.0 as usize, nodes, interned)));
+
+    let (literal, argument) = match &node.data {
+        NodeData::Root => (None, None),
+        NodeData::Literal { name } => (Some(*name), None),
+        NodeData::Argument {
+            name,
+            parser,
+            suggestion,
+        } => (
+            None,
+            Some((
+                *name,
+                ParserKey::new(parser),
+                suggestion.map(|suggestion| suggestion as u8),
+            )),
+        ),
+    };
+
+    let key = NodeKey {
+        literal,
+        argument,
+        executable: node.executable,
+        redirect_node: redirect_node.map(|target| target.0),
+        children: children.iter().map(|child| child.0).collect(),
+    };
+
+    *interned.entry(key).or_insert_with(|| {
+        nodes.push(Node {
+            children,
+            data: node.data.clone(),
+            executable: node.executable,
+            redirect_node,
+        });
+
+        nodes.len() as i32 - 1
+    })
+}
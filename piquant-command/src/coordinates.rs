@@ -0,0 +1,208 @@
+use std::str::FromStr;
+
+use valence_protocol::BlockPos;
+
+use crate::Origin;
+
+/// A single coordinate as typed by the player.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Coordinate {
+    /// `5`
+    Absolute(f64),
+    /// `~5`, relative to the sender's position.
+    Relative(f64),
+    /// `^5`, relative to where the sender is looking.
+    Local(f64),
+}
+
+impl FromStr for Coordinate {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // `NaN` and `inf` parse as numbers, but are no place to be.
+        let number = |n: &str| {
+            n.parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite())
+                .ok_or_else(|| format!("expected a coordinate got {s}"))
+        };
+        let offset = |n: &str| match n {
+            "" => Ok(0.0),
+            _ => number(n),
+        };
+
+        if let Some(rest) = s.strip_prefix('~') {
+            Ok(Self::Relative(offset(rest)?))
+        } else if let Some(rest) = s.strip_prefix('^') {
+            Ok(Self::Local(offset(rest)?))
+        } else {
+            Ok(Self::Absolute(number(s)?))
+        }
+    }
+}
+
+/// Three coordinates, either all local or none of them.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Coordinates([Coordinate; 3]);
+
+impl Coordinates {
+    pub fn new(coordinates: [Coordinate; 3]) -> Result<Self, Box<dyn std::error::Error>> {
+        let local = coordinates
+            .iter()
+            .filter(|c| matches!(c, Coordinate::Local(_)))
+            .count();
+
+        if local != 0 && local != 3 {
+            return Err(
+                "Cannot mix world & local coordinates (everything must either use ^ or not)".into(),
+            );
+        }
+
+        Ok(Self(coordinates))
+    }
+
+    /// Resolves the coordinates against the position and rotation of the
+    /// sender. Local coordinates start at the eyes of the sender.
+    pub fn resolve(&self, origin: &Origin) -> [f64; 3] {
+        let position = origin.position;
+
+        if let [Coordinate::Local(left), Coordinate::Local(up), Coordinate::Local(forward)] = self.0
+        {
+            let (yaw, pitch) = (origin.yaw as f64, origin.pitch as f64);
+            let eyes = [position[0], position[1] + origin.eye_height, position[2]];
+
+            let f = (yaw + 90.0).to_radians().cos();
+            let g = (yaw + 90.0).to_radians().sin();
+            let h = (-pitch).to_radians().cos();
+            let i = (-pitch).to_radians().sin();
+            let j = (-pitch + 90.0).to_radians().cos();
+            let k = (-pitch + 90.0).to_radians().sin();
+
+            let forward_dir = [f * h, i, g * h];
+            let up_dir = [f * j, k, g * j];
+            // -(forward x up)
+            let left_dir = [
+                -(forward_dir[1] * up_dir[2] - forward_dir[2] * up_dir[1]),
+                -(forward_dir[2] * up_dir[0] - forward_dir[0] * up_dir[2]),
+                -(forward_dir[0] * up_dir[1] - forward_dir[1] * up_dir[0]),
+            ];
+
+            return [0, 1, 2]
+                .map(|a| eyes[a] + forward_dir[a] * forward + up_dir[a] * up + left_dir[a] * left);
+        }
+
+        [0, 1, 2].map(|a| match self.0[a] {
+            Coordinate::Absolute(v) => v,
+            Coordinate::Relative(v) | Coordinate::Local(v) => position[a] + v,
+        })
+    }
+
+    /// Resolves the coordinates to the block they are in.
+    pub fn block_pos(&self, origin: &Origin) -> BlockPos {
+        let [x, y, z] = self.resolve(origin);
+
+        BlockPos::new(x.floor() as i32, y.floor() as i32, z.floor() as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coordinates(input: &str) -> Result<Coordinates, Box<dyn std::error::Error>> {
+        let words: Vec<Coordinate> = input.split(' ').map(str::parse).collect::<Result<_, _>>()?;
+
+        Coordinates::new(words.try_into().unwrap())
+    }
+
+    fn close(a: [f64; 3], b: [f64; 3]) -> bool {
+        (0..3).all(|i| (a[i] - b[i]).abs() < 1e-9)
+    }
+
+    #[test]
+    fn parses_each_kind() {
+        assert_eq!(
+            "5".parse::<Coordinate>().unwrap(),
+            Coordinate::Absolute(5.0)
+        );
+        assert_eq!(
+            "~".parse::<Coordinate>().unwrap(),
+            Coordinate::Relative(0.0)
+        );
+        assert_eq!(
+            "~-2.5".parse::<Coordinate>().unwrap(),
+            Coordinate::Relative(-2.5)
+        );
+        assert_eq!("^1".parse::<Coordinate>().unwrap(), Coordinate::Local(1.0));
+    }
+
+    #[test]
+    fn rejects_bad_numbers() {
+        for input in ["", "~x", "^^", "NaN", "~inf", "^-infinity"] {
+            assert!(input.parse::<Coordinate>().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn local_cannot_be_mixed() {
+        assert!(coordinates("^ ~ ^").is_err());
+        assert!(coordinates("^ 1 ^").is_err());
+        assert!(coordinates("^ ^ ^").is_ok());
+    }
+
+    #[test]
+    fn relative_resolves_against_position() {
+        let origin = Origin {
+            position: [10.0, 64.0, -3.0],
+            eye_height: 1.62,
+            ..Default::default()
+        };
+
+        let resolved = coordinates("1 ~ ~2").unwrap().resolve(&origin);
+        assert_eq!(resolved, [1.0, 64.0, -1.0]);
+    }
+
+    #[test]
+    fn local_starts_at_the_eyes() {
+        let origin = Origin {
+            position: [0.0, 64.0, 0.0],
+            eye_height: 1.62,
+            ..Default::default()
+        };
+
+        assert!(close(
+            coordinates("^ ^ ^").unwrap().resolve(&origin),
+            [0.0, 65.62, 0.0]
+        ));
+
+        // Yaw 0 looks towards positive z, and left is positive x.
+        assert!(close(
+            coordinates("^ ^ ^2").unwrap().resolve(&origin),
+            [0.0, 65.62, 2.0]
+        ));
+        assert!(close(
+            coordinates("^1 ^ ^").unwrap().resolve(&origin),
+            [1.0, 65.62, 0.0]
+        ));
+
+        let looking_up = Origin {
+            pitch: -90.0,
+            ..origin
+        };
+        assert!(close(
+            coordinates("^ ^ ^1").unwrap().resolve(&looking_up),
+            [0.0, 66.62, 0.0]
+        ));
+    }
+
+    #[test]
+    fn block_pos_floors() {
+        let origin = Origin {
+            position: [0.5, 64.0, -0.5],
+            ..Default::default()
+        };
+
+        let pos = coordinates("~ ~-0.5 ~").unwrap().block_pos(&origin);
+        assert_eq!(pos, BlockPos::new(0, 63, -1));
+    }
+}
@@ -0,0 +1,118 @@
+use std::{fmt::Display, ops::Range};
+
+use thiserror::Error;
+use valence_protocol::text::{Color, Text, TextFormat};
+
+/// How many characters before the error are shown, like in vanilla.
+const CONTEXT_LENGTH: usize = 10;
+
+/// Why a command did not run. Errors in the input know the byte range they
+/// refer to, see `render`.
+#[derive(Debug, Error)]
+pub enum CommandError {
+    #[error("Unknown or incomplete command")]
+    UnknownCommand { span: Range<usize> },
+    #[error("{message}")]
+    Syntax { message: String, cursor: usize },
+    #[error("Invalid {name}, expected {expected}: {message}")]
+    InvalidArgument {
+        name: &'static str,
+        expected: String,
+        message: String,
+        span: Range<usize>,
+    },
+    #[error("You don't have permission to use this command")]
+    PermissionDenied,
+    /// The command itself failed.
+    #[error("{0}")]
+    Execution(Box<dyn std::error::Error>),
+}
+
+impl CommandError {
+    pub fn syntax(message: impl Into<String>, cursor: usize) -> Self {
+        Self::Syntax {
+            message: message.into(),
+            cursor,
+        }
+    }
+
+    /// A required argument that was not typed.
+    pub fn missing_argument(name: &'static str, expected: &str, cursor: usize) -> Self {
+        Self::syntax(format!("Expected {expected} for {name}"), cursor)
+    }
+
+    pub fn invalid_argument(
+        name: &'static str,
+        expected: impl Into<String>,
+        message: impl Display,
+        span: Range<usize>,
+    ) -> Self {
+        Self::InvalidArgument {
+            name,
+            expected: expected.into(),
+            message: message.to_string(),
+            span,
+        }
+    }
+
+    /// The part of the input the error is about.
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            Self::UnknownCommand { span } | Self::InvalidArgument { span, .. } => {
+                Some(span.clone())
+            }
+            Self::Syntax { cursor, .. } => Some(*cursor..*cursor),
+            Self::PermissionDenied | Self::Execution(_) => None,
+        }
+    }
+
+    /// Renders the error like vanilla: the message, followed by the end of the
+    /// input before the error with the bad part underlined and a `<--[HERE]`
+    /// marker. Clicking the input puts it back into the chat box.
+    pub fn render(&self, input: &str) -> Text {
+        let message = self.to_string().color(Color::RED);
+
+        let Some(span) = self.span() else {
+            return message;
+        };
+
+        let start = floor_char_boundary(input, span.start);
+        let end = floor_char_boundary(input, span.end.max(start));
+
+        let before = &input[..start];
+        let skipped = before.chars().count().saturating_sub(CONTEXT_LENGTH);
+
+        let mut context = Text::text("")
+            .color(Color::GRAY)
+            .on_click_suggest_command(format!("/{}", input.trim_start_matches('/')));
+
+        if skipped > 0 {
+            context += "...";
+        }
+
+        context += before.chars().skip(skipped).collect::<String>();
+
+        if start < end {
+            context += input[start..end].to_string().color(Color::RED).underlined();
+        }
+
+        context += "<--[HERE]".color(Color::RED).italic();
+
+        message + "\n" + context
+    }
+}
+
+/// Two commands with the same name or alias.
+#[derive(Debug, Error)]
+#[error("The command /{0} is defined more than once")]
+pub struct DuplicateCommand(pub String);
+
+fn floor_char_boundary(s: &str, mut i: usize) -> usize {
+    i = i.min(s.len());
+
+    while !s.is_char_boundary(i) {
+        i -= 1;
+    }
+
+    i
+}
@@ -0,0 +1,146 @@
+use valence_protocol::text::{Color, Text, TextFormat};
+
+use crate::{ArgumentDef, CommandDef, CommandError};
+
+/// The number of commands on each page of `/help`.
+pub const HELP_PAGE_SIZE: usize = 8;
+
+/// The usage of a command or subcommand, e.g. `/time set <value>`.
+pub fn usage(prefix: &str, def: &CommandDef) -> String {
+    arguments_usage(&format!("/{prefix}{}", def.name), &def.arguments)
+}
+
+fn arguments_usage(command: &str, args: &[ArgumentDef]) -> String {
+    let mut usage = command.to_string();
+
+    for arg in args {
+        usage.push(' ');
+        usage.push_str(&argument_usage(arg));
+    }
+
+    usage
+}
+
+/// `<name>` for required and `[name]` for optional arguments, literals are
+/// listed instead of the name, or before it if other words are accepted too.
+fn argument_usage(arg: &ArgumentDef) -> String {
+    let name = match (arg.literals.is_empty(), arg.other_words) {
+        (true, _) => arg.name.to_string(),
+        (false, true) => format!("{}|{}", arg.literals.join("|"), arg.name),
+        (false, false) => arg.literals.join("|"),
+    };
+
+    match arg.optional {
+        true => format!("[{name}]"),
+        false => format!("<{name}>"),
+    }
+}
+
+/// One page of the list of commands, pages start at 1.
+pub fn help_page(defs: &[&CommandDef], page: usize) -> Result<Vec<Text>, CommandError> {
+    let pages = defs.len().div_ceil(HELP_PAGE_SIZE).max(1);
+
+    if page == 0 || page > pages {
+        return Err(CommandError::Execution(
+            format!("There is no page {page}, pages go from 1 to {pages}").into(),
+        ));
+    }
+
+    let mut lines = vec![format!("--- Help, page {page} of {pages} ---").color(Color::GOLD)];
+
+    for def in defs
+        .iter()
+        .skip((page - 1) * HELP_PAGE_SIZE)
+        .take(HELP_PAGE_SIZE)
+    {
+        let mut line = format!("/{}", def.name)
+            .color(Color::YELLOW)
+            .on_click_run_command(format!("/help {}", def.name));
+
+        if let Some(summary) = summary(def) {
+            line += format!(": {summary}").color(Color::GRAY);
+        }
+
+        lines.push(line);
+    }
+
+    if page < pages {
+        lines.push(
+            format!("Type /help {} for the next page", page + 1)
+                .color(Color::GRAY)
+                .on_click_run_command(format!("/help {}", page + 1)),
+        );
+    }
+
+    Ok(lines)
+}
+
+/// The description, usage and arguments of a command.
+pub fn help_command(def: &CommandDef) -> Vec<Text> {
+    let mut lines = vec![format!("--- /{} ---", def.name).color(Color::GOLD)];
+
+    if let Some(description) = &def.description {
+        lines.extend(description.lines().map(|line| line.to_string().into()));
+    }
+
+    if !def.overloads.is_empty() {
+        // Overloads are typed after the name of the command, not their own.
+        let command = format!("/{}", def.name);
+
+        for overload in &def.overloads {
+            lines.extend(usage_lines(&command, overload));
+
+            if let Some(summary) = summary(overload) {
+                lines.push(format!("  {summary}").color(Color::GRAY));
+            }
+        }
+    } else if def.subcommands.is_empty() {
+        lines.extend(usage_lines(&format!("/{}", def.name), def));
+    } else {
+        for sub in &def.subcommands {
+            lines.extend(usage_lines(&format!("/{} {}", def.name, sub.name), sub));
+
+            if let Some(summary) = summary(sub) {
+                lines.push(format!("  {summary}").color(Color::GRAY));
+            }
+        }
+    }
+
+    lines
+}
+
+/// The usage of the arguments of `def` after `command`, followed by one line
+/// per argument.
+fn usage_lines(command: &str, def: &CommandDef) -> Vec<Text> {
+    let usage = arguments_usage(command, &def.arguments);
+
+    let mut lines = vec![usage
+        .clone()
+        .color(Color::YELLOW)
+        .on_click_suggest_command(usage)];
+
+    for arg in &def.arguments {
+        let ty = match (arg.literals.is_empty(), arg.other_words) {
+            (true, _) => arg.ty.to_string(),
+            (false, true) => format!("{} or one of {}", arg.ty, arg.literals.join(", ")),
+            (false, false) => format!("one of {}", arg.literals.join(", ")),
+        };
+        let optional = if arg.optional { ", optional" } else { "" };
+
+        let mut line = format!("  {} ", arg.name).color(Color::WHITE)
+            + format!("({ty}{optional})").color(Color::DARK_GRAY);
+
+        if let Some(description) = arg.description {
+            line += format!(" {description}").color(Color::GRAY);
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// The first line of the description of a command.
+fn summary(def: &CommandDef) -> Option<&str> {
+    def.description.as_deref()?.lines().next()
+}
@@ -1,29 +1,98 @@
+mod argument;
 mod command_service;
 mod command_stack;
+mod coordinates;
+mod error;
+mod help;
+mod packets;
 mod parser;
-mod tokenizer;
+mod registry;
+mod selector;
+mod suggestions;
 
-use std::collections::VecDeque;
-
-pub use command_service::CommandService;
+pub use argument::{Bounded, FromArgument, Greedy, Origin, Rotation, Time, Word};
+pub use command_service::{CommandChange, CommandService};
+pub use coordinates::{Coordinate, Coordinates};
+pub use error::{CommandError, DuplicateCommand};
+pub use help::{help_command, help_page, usage, HELP_PAGE_SIZE};
+pub use packets::{CommandSuggestionsResponse, SuggestionMatch};
 
 pub use parser::parse;
-pub use parser::Argument;
+pub use parser::Arguments;
+pub use registry::Registration;
+pub use selector::{EntitySelector, Filter, SelectorTarget, Sort};
+pub use suggestions::{Suggestion, SuggestionContext, SuggestionProvider};
+pub use valence_protocol::packets::s2c::commands::{Parser, StringArg};
+
+// Used by `#[command]` to register commands and check `#[arg(regex = ..)]`.
+#[doc(hidden)]
+pub use inventory;
+#[doc(hidden)]
+pub use regex;
 
-pub type Arguments = VecDeque<Argument>;
+/// A command run by a sender `C`, with the game `G` and the world or server
+/// `W` it changes.
 pub type Command<G, C, W> =
     fn(Arguments, &G, &mut C, &mut W) -> Result<(), Box<dyn std::error::Error>>;
 
+/// The game, sender and server types the commands of a server run with.
+/// `#[command]` finds the types a command doesn't take through the ones it
+/// does, so the game and sender types both implement it.
+pub trait CommandContext {
+    type Game;
+    type Sender;
+    type Server;
+}
+
 #[derive(Debug)]
 pub struct ArgumentDef {
     pub name: &'static str,
+    /// The name of the type, see `FromArgument::NAME`.
     pub ty: &'static str,
+    pub parser: Parser<'static>,
+    /// The number of words the argument is typed as.
+    pub words: usize,
     pub optional: bool,
+    /// The words the argument is limited to, sent to clients as literals.
+    pub literals: Vec<&'static str>,
+    /// Whether words other than `literals` are accepted too, typed as
+    /// `parser`.
+    pub other_words: bool,
+    /// The name of the provider the client asks for suggestions, if any.
+    pub suggestions: Option<&'static str>,
+    /// From the `* name: description` line in the doc comment of the command.
+    pub description: Option<&'static str>,
 }
 
 #[derive(Debug)]
 pub struct CommandDef {
     pub name: &'static str,
+    /// The doc comment of the command, the first line is its summary.
     pub description: Option<String>,
     pub arguments: Vec<ArgumentDef>,
+    /// Literal branches of a command group, e.g. `set` in `/time set`.
+    pub subcommands: Vec<CommandDef>,
+    /// The argument lists of a command that takes different arguments, e.g.
+    /// `/tp <location>` and `/tp <targets> <destination>`. They branch right
+    /// after the name of the command.
+    pub overloads: Vec<CommandDef>,
+    /// The permission node that allows running the command, e.g.
+    /// `piquant.command.setblock`.
+    pub permission: Option<&'static str>,
+    /// The op level that allows running the command without the node.
+    pub op_level: i32,
+    /// Other names of the command, sent to clients as redirects.
+    pub aliases: Vec<&'static str>,
+}
+
+impl CommandDef {
+    /// Returns `true` if anyone may run the command.
+    pub fn is_public(&self) -> bool {
+        self.permission.is_none() && self.op_level == 0
+    }
+}
+
+/// Decides who may run commands that need a permission.
+pub trait PermissionCheck<C> {
+    fn has_permission(&self, sender: &C, node: Option<&str>, op_level: i32) -> bool;
 }
@@ -0,0 +1,20 @@
+use valence_protocol::{Encode, EncodePacket, Text, VarInt};
+
+// Clientbound packets that are not (yet) provided by valence.
+
+/// Answers a `CommandSuggestionsRequest`, replacing `length` characters of the
+/// typed command from `start` with one of the matches.
+#[derive(Clone, Debug, Encode, EncodePacket)]
+#[packet_id = 0x0d]
+pub struct CommandSuggestionsResponse {
+    pub transaction_id: VarInt,
+    pub start: VarInt,
+    pub length: VarInt,
+    pub matches: Vec<SuggestionMatch>,
+}
+
+#[derive(Clone, Debug, Encode)]
+pub struct SuggestionMatch {
+    pub suggestion: String,
+    pub tooltip: Option<Text>,
+}
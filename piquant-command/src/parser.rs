@@ -1,137 +1,179 @@
-use std::collections::VecDeque;
+use std::ops::Range;
 
-use crate::tokenizer::Token;
+use valence_protocol::packets::s2c::commands::StringArg;
+
+use crate::CommandError;
 
 #[derive(Debug)]
 pub struct Ident(pub String);
 
-#[derive(Debug)]
-pub enum Argument {
-    Integer(i64),
-    Float(f64),
-    String(String),
-    Boolean(bool),
-    OptionalInteger(Option<i64>),
-    OptionalFloat(Option<f64>),
-    OptionalString(Option<String>),
-    OptionalBoolean(Option<bool>),
+/// The input of a command after its name. The `FromArgument` impls of the
+/// parameters read one argument after another, each taking exactly what its
+/// type expects. The cursor and the span of the last argument are kept for
+/// errors.
+#[derive(Clone, Debug)]
+pub struct Arguments {
+    input: String,
+    cursor: usize,
+    span: Range<usize>,
 }
 
-impl TryFrom<Argument> for i64 {
-    type Error = Box<dyn std::error::Error>;
-    fn try_from(arg: Argument) -> Result<Self, Self::Error> {
-        match arg {
-            Argument::Integer(i) => Ok(i),
-            _ => return Err(format!("expected `Integer` got {:?}", arg).into()),
+impl Arguments {
+    /// Arguments that start at `cursor`, the span starts out as everything
+    /// before it.
+    pub fn new(input: impl Into<String>, cursor: usize) -> Self {
+        Self {
+            input: input.into(),
+            cursor,
+            span: 0..cursor,
         }
     }
-}
 
-impl TryFrom<Argument> for f64 {
-    type Error = Box<dyn std::error::Error>;
-    fn try_from(arg: Argument) -> Result<Self, Self::Error> {
-        match arg {
-            Argument::Integer(i) => Ok(i as f64),
-            Argument::Float(f) => Ok(f),
-            _ => return Err(format!("expected `Float` got {:?}", arg).into()),
-        }
+    /// The whole command line.
+    pub fn input(&self) -> &str {
+        &self.input
     }
-}
 
-impl TryFrom<Argument> for String {
-    type Error = Box<dyn std::error::Error>;
-    fn try_from(arg: Argument) -> Result<Self, Self::Error> {
-        match arg {
-            Argument::Integer(i) => Ok(i.to_string()),
-            Argument::Float(f) => Ok(f.to_string()),
-            Argument::String(s) => Ok(s),
-            Argument::Boolean(b) => Ok(b.to_string()),
-            _ => return Err(format!("expected `String` got {:?}", arg).into()),
-        }
+    /// Where reading continues.
+    pub fn cursor(&self) -> usize {
+        self.cursor
     }
-}
 
-impl TryFrom<Argument> for bool {
-    type Error = Box<dyn std::error::Error>;
-    fn try_from(arg: Argument) -> Result<Self, Self::Error> {
-        match arg {
-            Argument::Integer(i) if i == 0 => Ok(false),
-            Argument::Integer(i) if i == 1 => Ok(true),
-            Argument::Boolean(b) => Ok(b),
-            _ => return Err(format!("expected `Boolean` got {:?}", arg).into()),
-        }
+    /// Where the argument read last was typed, the command name before any
+    /// was read and the end of the input once a read found nothing.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
     }
-}
 
-impl TryFrom<Argument> for Option<i64> {
-    type Error = Box<dyn std::error::Error>;
-    fn try_from(arg: Argument) -> Result<Self, Self::Error> {
-        match arg {
-            Argument::Integer(i) => Ok(Some(i)),
-            Argument::OptionalInteger(i) => Ok(i),
-            _ => return Err(format!("expected `OptionalInteger` got {:?}", arg).into()),
-        }
+    /// Where the input ends.
+    pub fn end(&self) -> usize {
+        self.input.trim_end().len()
     }
-}
 
-impl TryFrom<Argument> for Option<f64> {
-    type Error = Box<dyn std::error::Error>;
-    fn try_from(arg: Argument) -> Result<Self, Self::Error> {
-        match arg {
-            Argument::Integer(i) => Ok(Some(i as f64)),
-            Argument::Float(f) => Ok(Some(f)),
-            Argument::OptionalFloat(f) => Ok(f),
-            _ => return Err(format!("expected `OptionalFloat` got {:?}", arg).into()),
-        }
+    /// Where the arguments that were not read yet start and end.
+    pub fn remaining(&self) -> Option<Range<usize>> {
+        let start = self.next_start();
+        (start < self.end()).then(|| start..self.end())
     }
-}
 
-impl TryFrom<Argument> for Option<String> {
-    type Error = Box<dyn std::error::Error>;
-    fn try_from(arg: Argument) -> Result<Self, Self::Error> {
-        match arg {
-            Argument::Integer(i) => Ok(Some(i.to_string())),
-            Argument::Float(f) => Ok(Some(f.to_string())),
-            Argument::String(s) => Ok(Some(s)),
-            Argument::Boolean(b) => Ok(Some(b.to_string())),
-            Argument::OptionalString(s) => Ok(s),
-            _ => return Err(format!("expected `OptionalString` got {:?}", arg).into()),
-        }
+    pub fn is_empty(&self) -> bool {
+        self.remaining().is_none()
+    }
+
+    /// Where the next argument starts, after the spaces at the cursor.
+    fn next_start(&self) -> usize {
+        let rest = &self.input[self.cursor..];
+        self.cursor + rest.len() - rest.trim_start().len()
+    }
+
+    fn take(&mut self, start: usize, end: usize) -> &str {
+        self.cursor = end;
+        self.span = start..end;
+        &self.input[start..end]
+    }
+
+    /// Reads up to the next space. Spaces inside brackets are kept, so the
+    /// options of selectors like `@e[name="a b"]` are part of the word.
+    pub fn read_word(&mut self) -> &str {
+        let start = self.next_start();
+        let end = self.word_end(start);
+        self.take(start, end)
     }
-}
 
-impl TryFrom<Argument> for Option<bool> {
-    type Error = Box<dyn std::error::Error>;
-    fn try_from(arg: Argument) -> Result<Self, Self::Error> {
-        match arg {
-            Argument::Integer(i) if i == 0 => Ok(Some(false)),
-            Argument::Integer(i) if i == 1 => Ok(Some(true)),
-            Argument::Boolean(b) => Ok(Some(b)),
-            Argument::OptionalBoolean(b) => Ok(b),
-            _ => return Err(format!("expected `OptionalBoolean` got {:?}", arg).into()),
+    fn word_end(&self, start: usize) -> usize {
+        let mut end = self.input.len();
+        let mut depth = 0usize;
+        let mut in_quotes = false;
+
+        for (i, c) in self.input[start..].char_indices() {
+            match c {
+                c if c.is_whitespace() && depth == 0 && !in_quotes => {
+                    end = start + i;
+                    break;
+                }
+                '"' if depth > 0 => in_quotes = !in_quotes,
+                '[' | '{' if !in_quotes => depth += 1,
+                ']' | '}' if !in_quotes => depth = depth.saturating_sub(1),
+                _ => {}
+            }
         }
+
+        end
     }
-}
 
-pub fn parse(input: &str) -> Result<(Ident, VecDeque<Argument>), Box<dyn std::error::Error>> {
-    let mut tokens = crate::tokenizer::tokenize(input);
+    /// Reads everything up to the end of the input.
+    pub fn read_greedy(&mut self) -> &str {
+        let start = self.next_start();
+        let end = self.end().max(start);
+
+        self.take(start, end)
+    }
 
-    let mut args = VecDeque::new();
+    /// Reads a string surrounded by double or single quotes where `\` escapes
+    /// the next character, or a single word if it doesn't start with a quote.
+    pub fn read_quotable(&mut self) -> Result<String, CommandError> {
+        let start = self.next_start();
+
+        let Some(quote) = self.input[start..]
+            .chars()
+            .next()
+            .filter(|c| matches!(c, '"' | '\''))
+        else {
+            return Ok(self.read_word().to_string());
+        };
+
+        let mut string = String::new();
+        let mut chars = self.input[start + 1..].char_indices();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, c)) => string.push(c),
+                    None => break,
+                },
+                c if c == quote => {
+                    let end = start + 1 + i + c.len_utf8();
+
+                    if self.input[end..]
+                        .chars()
+                        .next()
+                        .is_some_and(|c| !c.is_whitespace())
+                    {
+                        return Err(CommandError::syntax(
+                            "Expected whitespace to end one argument, but found trailing data",
+                            end,
+                        ));
+                    }
+
+                    self.take(start, end);
+                    return Ok(string);
+                }
+                c => string.push(c),
+            }
+        }
 
-    let cmd: Ident = match tokens.pop_front() {
-        Some(Token::UnquotedString(s)) => Ident(s),
-        _ => return Err("Invalid command".into()),
-    };
+        Err(CommandError::syntax("Unclosed quoted string", self.end()))
+    }
 
-    while let Some(token) = tokens.pop_front() {
-        match token {
-            Token::Integer(i) => args.push_back(Argument::Integer(i)),
-            Token::Float(f) => args.push_back(Argument::Float(f)),
-            Token::String(s) => args.push_back(Argument::String(s)),
-            Token::Boolean(b) => args.push_back(Argument::Boolean(b)),
-            Token::UnquotedString(s) => args.push_back(Argument::String(s)),
+    /// Reads a string the way clients parse `mode`.
+    pub fn read_string(&mut self, mode: StringArg) -> Result<String, CommandError> {
+        match mode {
+            StringArg::SingleWord => Ok(self.read_word().to_string()),
+            StringArg::QuotablePhrase => self.read_quotable(),
+            StringArg::GreedyPhrase => Ok(self.read_greedy().to_string()),
         }
     }
+}
+
+/// Reads the name of the command, the arguments are left to the command.
+pub fn parse(input: &str) -> Result<(Ident, Arguments), CommandError> {
+    let mut args = Arguments::new(input, 0);
+
+    if args.is_empty() {
+        return Err(CommandError::UnknownCommand { span: 0..0 });
+    }
+
+    let name = args.read_word().to_string();
 
-    Ok((cmd, args))
+    Ok((Ident(name), args))
 }
@@ -0,0 +1,14 @@
+use std::any::Any;
+
+use crate::DuplicateCommand;
+
+/// A `#[command]` function or group, collected from the whole program so
+/// that `CommandService::with_registered` can add it.
+pub struct Registration {
+    pub name: &'static str,
+    /// Adds the command to a `CommandService`, returns `None` if the service
+    /// is not of the types the command was written for.
+    pub register: fn(&mut dyn Any) -> Option<Result<(), DuplicateCommand>>,
+}
+
+inventory::collect!(Registration);
@@ -0,0 +1,426 @@
+use valence_protocol::{types::GameMode, Uuid};
+
+/// A condition of a selector that can be inverted with `!`, e.g. `type=!cow`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Filter<T> {
+    pub value: T,
+    pub negated: bool,
+}
+
+impl<T: PartialEq> Filter<T> {
+    fn matches(&self, value: Option<&T>) -> bool {
+        (value == Some(&self.value)) != self.negated
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Sort {
+    #[default]
+    Arbitrary,
+    Nearest,
+    Furthest,
+    Random,
+}
+
+/// Something a selector can pick, either a player or another entity.
+pub trait SelectorTarget {
+    fn uuid(&self) -> Uuid;
+    /// The entity type without namespace, e.g. `cow`.
+    fn kind(&self) -> &str;
+    fn position(&self) -> [f64; 3];
+    /// The username of a player, `None` for other entities.
+    fn name(&self) -> Option<&str>;
+    /// The game mode of a player, `None` for other entities.
+    fn game_mode(&self) -> Option<GameMode>;
+    fn has_tag(&self, tag: &str) -> bool;
+    fn has_any_tag(&self) -> bool;
+
+    fn is_player(&self) -> bool {
+        self.game_mode().is_some()
+    }
+}
+
+/// A target selector like `@a`, `@e[type=cow,distance=..10]`, a player name
+/// or a UUID.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct EntitySelector {
+    pub players_only: bool,
+    /// `@s`, only the sender.
+    pub sender_only: bool,
+    pub uuid: Option<Uuid>,
+    pub types: Vec<Filter<String>>,
+    pub names: Vec<Filter<String>>,
+    pub game_modes: Vec<Filter<GameMode>>,
+    /// `tag=` without a value matches entities without any tag.
+    pub tags: Vec<Filter<String>>,
+    pub min_distance: Option<f64>,
+    pub max_distance: Option<f64>,
+    /// Replaces the sender's position as the origin.
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub z: Option<f64>,
+    /// The size of the volume entities have to be in, starting at the origin.
+    pub dx: Option<f64>,
+    pub dy: Option<f64>,
+    pub dz: Option<f64>,
+    pub limit: Option<usize>,
+    pub sort: Sort,
+}
+
+impl EntitySelector {
+    /// Parses a selector as the client would.
+    pub fn parse(input: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let Some(rest) = input.strip_prefix('@') else {
+            if let Ok(uuid) = Uuid::parse_str(input) {
+                return Ok(Self {
+                    uuid: Some(uuid),
+                    limit: Some(1),
+                    ..Default::default()
+                });
+            }
+
+            return Ok(Self {
+                players_only: true,
+                names: vec![Filter {
+                    value: input.to_string(),
+                    negated: false,
+                }],
+                limit: Some(1),
+                ..Default::default()
+            });
+        };
+
+        let (variable, args) = match rest.split_once('[') {
+            Some((variable, args)) => match args.strip_suffix(']') {
+                Some(args) => (variable, args),
+                None => return Err("Expected end of options".into()),
+            },
+            None => (rest, ""),
+        };
+
+        let mut selector = match variable {
+            "p" => Self {
+                players_only: true,
+                limit: Some(1),
+                sort: Sort::Nearest,
+                ..Default::default()
+            },
+            "a" => Self {
+                players_only: true,
+                ..Default::default()
+            },
+            "r" => Self {
+                players_only: true,
+                limit: Some(1),
+                sort: Sort::Random,
+                ..Default::default()
+            },
+            "s" => Self {
+                sender_only: true,
+                ..Default::default()
+            },
+            "e" => Self::default(),
+            _ => return Err(format!("Unknown selector type '@{variable}'").into()),
+        };
+
+        for option in args.split(',').filter(|option| !option.trim().is_empty()) {
+            let (key, value) = option
+                .split_once('=')
+                .ok_or_else(|| format!("Expected value for option '{}'", option.trim()))?;
+            let (key, value) = (key.trim(), value.trim());
+
+            let (negated, value) = match value.strip_prefix('!') {
+                Some(value) => (true, value),
+                None => (false, value),
+            };
+            let filter = |value: String| Filter { value, negated };
+
+            if negated && !matches!(key, "type" | "name" | "gamemode" | "tag") {
+                return Err(format!("Option '{key}' isn't applicable here").into());
+            }
+
+            match key {
+                "type" => {
+                    let kind = value.strip_prefix("minecraft:").unwrap_or(value);
+                    if kind == "player" && !negated {
+                        selector.players_only = true;
+                    }
+                    selector.types.push(filter(kind.to_string()));
+                }
+                "name" => selector
+                    .names
+                    .push(filter(value.trim_matches('"').to_string())),
+                "tag" => selector.tags.push(filter(value.to_string())),
+                "gamemode" => {
+                    let game_mode = match value {
+                        "survival" => GameMode::Survival,
+                        "creative" => GameMode::Creative,
+                        "adventure" => GameMode::Adventure,
+                        "spectator" => GameMode::Spectator,
+                        _ => return Err(format!("Invalid or unknown game mode '{value}'").into()),
+                    };
+                    selector.players_only = true;
+                    selector.game_modes.push(Filter {
+                        value: game_mode,
+                        negated,
+                    });
+                }
+                "distance" => {
+                    let (min, max) = parse_range(value)?;
+                    if min.is_some_and(|min| min < 0.0) || max.is_some_and(|max| max < 0.0) {
+                        return Err("Distance cannot be negative".into());
+                    }
+                    selector.min_distance = min;
+                    selector.max_distance = max;
+                }
+                "limit" => {
+                    let limit: usize = value.parse()?;
+                    if limit == 0 {
+                        return Err("Limit must be at least 1".into());
+                    }
+                    selector.limit = Some(limit);
+                }
+                "sort" => {
+                    selector.sort = match value {
+                        "nearest" => Sort::Nearest,
+                        "furthest" => Sort::Furthest,
+                        "random" => Sort::Random,
+                        "arbitrary" => Sort::Arbitrary,
+                        _ => return Err(format!("Invalid or unknown sort type '{value}'").into()),
+                    };
+                }
+                "x" => selector.x = Some(parse_number(value)?),
+                "y" => selector.y = Some(parse_number(value)?),
+                "z" => selector.z = Some(parse_number(value)?),
+                "dx" => selector.dx = Some(parse_number(value)?),
+                "dy" => selector.dy = Some(parse_number(value)?),
+                "dz" => selector.dz = Some(parse_number(value)?),
+                _ => return Err(format!("Unknown option '{key}'").into()),
+            }
+        }
+
+        Ok(selector)
+    }
+
+    /// The origin distances and volumes are measured from.
+    pub fn origin(&self, sender: [f64; 3]) -> [f64; 3] {
+        [
+            self.x.unwrap_or(sender[0]),
+            self.y.unwrap_or(sender[1]),
+            self.z.unwrap_or(sender[2]),
+        ]
+    }
+
+    /// The box entities have to be in, if the selector has a volume.
+    pub fn volume(&self, origin: [f64; 3]) -> Option<([f64; 3], [f64; 3])> {
+        if self.dx.is_none() && self.dy.is_none() && self.dz.is_none() {
+            return None;
+        }
+
+        let size = [self.dx, self.dy, self.dz].map(|d| d.unwrap_or(0.0));
+
+        Some((
+            [0, 1, 2].map(|a| origin[a] + size[a].min(0.0)),
+            [0, 1, 2].map(|a| origin[a] + size[a].max(0.0) + 1.0),
+        ))
+    }
+
+    /// Returns `true` if the target passes every filter of the selector.
+    /// Sorting and the limit are up to the caller.
+    pub fn matches(
+        &self,
+        target: &impl SelectorTarget,
+        origin: [f64; 3],
+        sender: Option<Uuid>,
+    ) -> bool {
+        if self.players_only && !target.is_player() {
+            return false;
+        }
+
+        if self.sender_only && sender != Some(target.uuid()) {
+            return false;
+        }
+
+        if self.uuid.is_some_and(|uuid| uuid != target.uuid()) {
+            return false;
+        }
+
+        let kind = target.kind().to_string();
+        if !self.types.iter().all(|f| f.matches(Some(&kind))) {
+            return false;
+        }
+
+        // Player names are case-insensitive.
+        let name = target.name();
+        if !self
+            .names
+            .iter()
+            .all(|f| name.is_some_and(|name| name.eq_ignore_ascii_case(&f.value)) != f.negated)
+        {
+            return false;
+        }
+
+        let game_mode = target.game_mode();
+        if !self
+            .game_modes
+            .iter()
+            .all(|f| f.matches(game_mode.as_ref()))
+        {
+            return false;
+        }
+
+        let tags_match = self.tags.iter().all(|f| {
+            let has = if f.value.is_empty() {
+                !target.has_any_tag()
+            } else {
+                target.has_tag(&f.value)
+            };
+            has != f.negated
+        });
+        if !tags_match {
+            return false;
+        }
+
+        let position = target.position();
+
+        if self.min_distance.is_some() || self.max_distance.is_some() {
+            let distance = distance(position, origin);
+
+            if self.min_distance.is_some_and(|min| distance < min)
+                || self.max_distance.is_some_and(|max| distance > max)
+            {
+                return false;
+            }
+        }
+
+        if let Some((min, max)) = self.volume(origin) {
+            if (0..3).any(|a| position[a] < min[a] || position[a] > max[a]) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+fn distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+    (0..3).map(|i| (a[i] - b[i]).powi(2)).sum::<f64>().sqrt()
+}
+
+/// Parses a finite number, `NaN` and `inf` would match nothing or everything.
+fn parse_number(value: &str) -> Result<f64, Box<dyn std::error::Error>> {
+    value
+        .parse()
+        .ok()
+        .filter(|number: &f64| number.is_finite())
+        .ok_or_else(|| format!("'{value}' is not a number").into())
+}
+
+/// Parses `5`, `..5`, `5..` or `1..5`.
+fn parse_range(value: &str) -> Result<(Option<f64>, Option<f64>), Box<dyn std::error::Error>> {
+    let bound = |s: &str| -> Result<Option<f64>, Box<dyn std::error::Error>> {
+        match s {
+            "" => Ok(None),
+            _ => Ok(Some(parse_number(s)?)),
+        }
+    };
+
+    match value.split_once("..") {
+        Some((min, max)) => {
+            let (min, max) = (bound(min)?, bound(max)?);
+            if min.zip(max).is_some_and(|(min, max)| min > max) {
+                return Err("Min cannot be bigger than max".into());
+            }
+            Ok((min, max))
+        }
+        None => {
+            let exact = bound(value)?;
+            Ok((exact, exact))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_and_uuids_select_one() {
+        let selector = EntitySelector::parse("Steve").unwrap();
+        assert!(selector.players_only);
+        assert_eq!(selector.names[0].value, "Steve");
+        assert_eq!(selector.limit, Some(1));
+
+        let uuid = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
+        let selector = EntitySelector::parse(uuid).unwrap();
+        assert_eq!(selector.uuid, Some(Uuid::parse_str(uuid).unwrap()));
+        assert_eq!(selector.limit, Some(1));
+    }
+
+    #[test]
+    fn variables_set_defaults() {
+        let nearest = EntitySelector::parse("@p").unwrap();
+        assert!(nearest.players_only);
+        assert_eq!(nearest.limit, Some(1));
+        assert_eq!(nearest.sort, Sort::Nearest);
+
+        assert!(EntitySelector::parse("@s").unwrap().sender_only);
+        assert!(!EntitySelector::parse("@e").unwrap().players_only);
+        assert!(EntitySelector::parse("@x").is_err());
+    }
+
+    #[test]
+    fn options_are_parsed() {
+        let selector =
+            EntitySelector::parse("@e[type=!minecraft:cow, tag=a, limit=3, sort=furthest, x=1.5]")
+                .unwrap();
+
+        assert_eq!(selector.types[0].value, "cow");
+        assert!(selector.types[0].negated);
+        assert_eq!(selector.tags[0].value, "a");
+        assert_eq!(selector.limit, Some(3));
+        assert_eq!(selector.sort, Sort::Furthest);
+        assert_eq!(selector.x, Some(1.5));
+    }
+
+    #[test]
+    fn bad_options_are_rejected() {
+        for input in [
+            "@e[limit=0]",
+            "@e[distance=-1]",
+            "@e[distance=5..1]",
+            "@e[x=!1]",
+            "@e[colour=red]",
+            "@e[type=cow",
+            "@e[tag]",
+        ] {
+            assert!(EntitySelector::parse(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn distance_ranges() {
+        let range = |input: &str| {
+            let selector = EntitySelector::parse(&format!("@e[distance={input}]")).unwrap();
+            (selector.min_distance, selector.max_distance)
+        };
+
+        assert_eq!(range("5"), (Some(5.0), Some(5.0)));
+        assert_eq!(range("..5"), (None, Some(5.0)));
+        assert_eq!(range("1.5.."), (Some(1.5), None));
+        assert_eq!(range("1..5"), (Some(1.0), Some(5.0)));
+    }
+
+    #[test]
+    fn numbers_must_be_finite() {
+        for input in [
+            "@e[x=NaN]",
+            "@e[dy=inf]",
+            "@e[z=-infinity]",
+            "@e[distance=..NaN]",
+            "@e[distance=inf..]",
+        ] {
+            assert!(EntitySelector::parse(input).is_err(), "{input}");
+        }
+    }
+}
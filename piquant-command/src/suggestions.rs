@@ -0,0 +1,94 @@
+use valence_protocol::{BlockKind, ItemKind};
+
+/// A completion for the argument being typed.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Suggestion {
+    pub text: String,
+    /// Shown when hovering the suggestion.
+    pub tooltip: Option<String>,
+}
+
+impl From<String> for Suggestion {
+    fn from(text: String) -> Self {
+        Self {
+            text,
+            tooltip: None,
+        }
+    }
+}
+
+impl From<&str> for Suggestion {
+    fn from(text: &str) -> Self {
+        text.to_string().into()
+    }
+}
+
+/// Everything a provider can suggest from.
+pub struct SuggestionContext<'a, G, C, W> {
+    pub game: &'a G,
+    pub client: &'a C,
+    pub world: &'a W,
+    /// The usernames of everyone online.
+    pub player_names: &'a [String],
+    pub world_names: &'a [String],
+}
+
+type SuggestFn<G, C, W> = dyn Fn(&SuggestionContext<G, C, W>) -> Vec<Suggestion> + Send + Sync;
+
+/// Where the suggestions of an argument come from. Arguments name their
+/// provider with `#[suggest("blocks")]`, see `CommandService::add_suggestions`
+/// for custom ones.
+pub enum SuggestionProvider<G, C, W> {
+    BlockKinds,
+    ItemKinds,
+    PlayerNames,
+    WorldNames,
+    Custom(Box<SuggestFn<G, C, W>>),
+}
+
+impl<G, C, W> SuggestionProvider<G, C, W> {
+    pub fn custom(
+        f: impl Fn(&SuggestionContext<G, C, W>) -> Vec<Suggestion> + Send + Sync + 'static,
+    ) -> Self {
+        Self::Custom(Box::new(f))
+    }
+
+    /// Returns the suggestions starting with `prefix`, ignoring case and the
+    /// `minecraft:` namespace.
+    pub fn suggest(&self, context: &SuggestionContext<G, C, W>, prefix: &str) -> Vec<Suggestion> {
+        let suggestions: Vec<Suggestion> = match self {
+            Self::BlockKinds => BlockKind::ALL
+                .iter()
+                .map(|kind| format!("minecraft:{}", kind.to_str()).into())
+                .collect(),
+            Self::ItemKinds => ItemKind::ALL
+                .iter()
+                .map(|kind| format!("minecraft:{}", kind.to_str()).into())
+                .collect(),
+            Self::PlayerNames => context
+                .player_names
+                .iter()
+                .map(|name| name.as_str().into())
+                .collect(),
+            Self::WorldNames => context
+                .world_names
+                .iter()
+                .map(|name| name.as_str().into())
+                .collect(),
+            Self::Custom(f) => f(context),
+        };
+
+        let prefix = prefix.to_lowercase();
+
+        suggestions
+            .into_iter()
+            .filter(|suggestion| {
+                let text = suggestion.text.to_lowercase();
+                text.starts_with(&prefix)
+                    || text
+                        .strip_prefix("minecraft:")
+                        .is_some_and(|text| text.starts_with(&prefix))
+            })
+            .collect()
+    }
+}
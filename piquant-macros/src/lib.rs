@@ -1,219 +1,904 @@
+use std::collections::HashMap;
+
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
-use proc_macro2::TokenTree::Literal;
 use quote::format_ident;
-use syn::{parse_macro_input, ItemFn};
+use syn::{
+    bracketed,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    Ident, Item, ItemFn, ItemMod, Lit, LitInt, LitStr, Meta, Token,
+};
+
+/// The type in `Option<T>`.
+fn option_inner(segment: &syn::PathSegment) -> syn::Result<Option<syn::Type>> {
+    if segment.ident != "Option" {
+        return Ok(None);
+    }
 
-fn parse_type(ident: &proc_macro2::Ident) -> String {
-    let ident = ident.to_string();
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(a) if a.args.len() == 1 => match &a.args[0] {
+            syn::GenericArgument::Type(t) => Ok(Some(t.clone())),
+            arg => Err(syn::Error::new_spanned(arg, "Expected a type")),
+        },
+        _ => Err(syn::Error::new_spanned(
+            segment,
+            "Option arguments must have a single type, e.g. Option<i64>",
+        )),
+    }
+}
 
-    match ident.as_str() {
-        "i64" => "Integer".to_string(),
-        "f64" => "Float".to_string(),
-        "String" => "String".to_string(),
-        "bool" => "Boolean".to_string(),
-        _ => "Unknown Type".to_string(),
+/// The first type argument of a path segment, e.g. `Game` in `Client<Game>`.
+fn type_argument(segment: &syn::PathSegment) -> Option<&syn::Type> {
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(a) => a.args.iter().find_map(|arg| match arg {
+            syn::GenericArgument::Type(t) => Some(t),
+            _ => None,
+        }),
+        _ => None,
     }
 }
 
-#[proc_macro_attribute]
-pub fn command(_: TokenStream, input: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(input as ItemFn);
+/// The last segment of the path of a type, e.g. `String` in
+/// `std::string::String`.
+fn last_segment(ty: &syn::Type) -> Option<&syn::PathSegment> {
+    match ty {
+        syn::Type::Path(p) if p.qself.is_none() => p.path.segments.last(),
+        _ => None,
+    }
+}
 
-    let fn_visiblity = &input.vis;
-    let fn_name = &input.sig.ident;
-    let fn_args = &input.sig.inputs;
-    let fn_ret = &input.sig.output;
-    let fn_body = &input.block;
+/// A doc comment split into the description and the descriptions of the
+/// arguments, written as `* name: description` lines.
+struct Docs {
+    description: Option<String>,
+    arguments: HashMap<String, String>,
+}
 
-    let fn_doc_attributes: &Vec<syn::Attribute> = &input
-        .attrs
+impl Docs {
+    fn description(&self) -> TokenStream2 {
+        match &self.description {
+            Some(description) => quote::quote! { Some(#description.to_string()) },
+            None => quote::quote! { None },
+        }
+    }
+
+    fn argument(&self, name: &str) -> TokenStream2 {
+        match self.arguments.get(name) {
+            Some(description) => quote::quote! { Some(#description) },
+            None => quote::quote! { None },
+        }
+    }
+}
+
+fn parse_doc(attrs: &[syn::Attribute]) -> Docs {
+    let lines = attrs
         .iter()
         .filter(|attr| attr.path.is_ident("doc"))
-        .cloned()
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(meta)) => match meta.lit {
+                Lit::Str(lit) => Some(lit.value()),
+                _ => None,
+            },
+            _ => None,
+        });
+
+    let mut paragraphs: Vec<String> = vec![String::new()];
+    let mut arguments = HashMap::new();
+
+    for line in lines {
+        let line = line.trim();
+
+        let argument = line
+            .strip_prefix("* ")
+            .and_then(|line| line.split_once(':'));
+
+        if let Some((name, description)) = argument {
+            let name = name.trim().trim_matches('`');
+            arguments.insert(name.to_string(), description.trim().to_string());
+        } else if line.is_empty() {
+            paragraphs.push(String::new());
+        } else {
+            // Lines of a paragraph are joined like rustdoc does.
+            let paragraph = paragraphs.last_mut().unwrap();
+            if !paragraph.is_empty() {
+                paragraph.push(' ');
+            }
+            paragraph.push_str(line);
+        }
+    }
+
+    paragraphs.retain(|paragraph| !paragraph.is_empty());
+
+    Docs {
+        description: (!paragraphs.is_empty()).then(|| paragraphs.join("\n")),
+        arguments,
+    }
+}
+
+/// Turns a function into a command, or a module into a group of commands.
+///
+/// Functions in a module that are marked with `#[subcommand]` become literal
+/// branches of the group, e.g. `/time set <value>` for `fn set` in `mod time`.
+/// Functions marked with `#[overload]` instead are argument lists the command
+/// takes right after its name, tried in the order they are written, e.g.
+/// `/tp <location>` and `/tp <targets> <destination>`.
+/// Arguments can be of any type implementing `piquant_command::FromArgument`,
+/// `Option`s of them are optional.
+/// String arguments marked with `#[literal("a", "b")]` only accept one of the
+/// given words. Arguments marked with `#[suggest("blocks")]` are completed by
+/// the named suggestion provider of the `CommandService`.
+///
+/// `#[arg(min = -64, max = 319)]` limits numbers, which clients are told
+/// about as well, `#[arg(regex = "^[a-z_]+$")]` limits strings and
+/// `#[arg(default = 1)]` makes an argument optional with a default value.
+/// They are checked before the function runs.
+///
+/// `#[command(permission = "piquant.command.time", level = 2)]` limits the
+/// command to players with the permission node or op level, the level is 2 by
+/// default for commands with a permission. `aliases = ["gm"]` adds other
+/// names for the command.
+///
+/// Commands take any of the game, the sender, the `Server<G>`, a `World<G>`
+/// or a `Client<G>`, marked with `#[context(game)]`, `#[context(sender)]`,
+/// `#[context(server)]`, `#[context(world)]` or `#[context(client)]`, so the
+/// types can have any name. The types of the ones a command doesn't take are
+/// found through `piquant_command::CommandContext`, which the game and sender
+/// types implement.
+///
+/// Commands register themselves, `CommandService::with_registered` builds a
+/// service with all of them.
+#[proc_macro_attribute]
+pub fn command(args: TokenStream, input: TokenStream) -> TokenStream {
+    let options = parse_macro_input!(args as CommandOptions);
+    let input = parse_macro_input!(input as Item);
+
+    let expanded = match input {
+        Item::Fn(input) => expand_command(input, &options, true).map(|(command, _)| command),
+        Item::Mod(input) => expand_group(input, &options),
+        item => Err(syn::Error::new_spanned(
+            item,
+            "#[command] can only be used on functions and modules",
+        )),
+    };
+
+    expanded
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements `FromArgument` for an enum of unit variants. The variants are
+/// typed as their names in snake case, e.g. `game_time` for `GameTime`.
+#[proc_macro_derive(FromArgument)]
+pub fn derive_from_argument(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let syn::Data::Enum(data) = &input.data else {
+        return syn::Error::new_spanned(name, "FromArgument can only be derived for enums")
+            .into_compile_error()
+            .into();
+    };
+
+    let variants: syn::Result<Vec<_>> = data
+        .variants
+        .iter()
+        .map(|variant| match variant.fields {
+            syn::Fields::Unit => Ok(&variant.ident),
+            _ => Err(syn::Error::new_spanned(
+                variant,
+                "FromArgument can only be derived for enums without fields",
+            )),
+        })
         .collect();
 
-    let fn_doc = fn_doc_attributes
+    let variants = match variants {
+        Ok(variants) => variants,
+        Err(e) => return e.into_compile_error().into(),
+    };
+
+    let words: Vec<_> = variants
         .iter()
-        .map(|attr| {
-            let tokens = attr.tokens.clone();
-
-            for token in tokens {
-                if let Literal(str) = token {
-                    let str = str.to_string();
-                    // remove surrounding doublequotes
-                    let str = str.trim_end_matches('"').trim_start_matches('"').trim();
-                    return Some(String::from(str));
+        .map(|variant| snake_case(&variant.to_string()))
+        .collect();
+    let expected = words.join(", ");
+
+    quote::quote! {
+        impl #impl_generics ::piquant_command::FromArgument for #name #ty_generics #where_clause {
+            const NAME: &'static str = #name_str;
+
+            fn parser() -> ::piquant_command::Parser<'static> {
+                ::piquant_command::Parser::String(::piquant_command::StringArg::SingleWord)
+            }
+
+            fn literals() -> Vec<&'static str> {
+                vec![#(#words),*]
+            }
+
+            fn from_argument(
+                args: &mut ::piquant_command::Arguments,
+                _: &::piquant_command::Origin,
+            ) -> Result<Self, Box<dyn std::error::Error>> {
+                let word = args.read_word();
+
+                match word {
+                    #(#words => Ok(Self::#variants),)*
+                    _ => Err(format!("expected one of {}, got {}", #expected, word).into()),
                 }
             }
-            None
-        })
-        .filter(|lit| lit.is_some())
-        .map(|a| a.unwrap().to_string())
-        .collect::<Vec<String>>();
+        }
+    }
+    .into()
+}
 
-    let fn_args = fn_args.iter().clone();
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i != 0 {
+            snake.push('_');
+        }
+        snake.extend(c.to_lowercase());
+    }
+
+    snake
+}
+
+#[derive(Default)]
+struct CommandOptions {
+    permission: Option<String>,
+    op_level: Option<i32>,
+    aliases: Vec<String>,
+}
+
+impl Parse for CommandOptions {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut options = Self::default();
+
+        while !input.is_empty() {
+            let name: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+
+            if name == "permission" {
+                options.permission = Some(input.parse::<LitStr>()?.value());
+            } else if name == "level" {
+                options.op_level = Some(input.parse::<LitInt>()?.base10_parse()?);
+            } else if name == "aliases" {
+                let content;
+                bracketed!(content in input);
+                options.aliases = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?
+                    .iter()
+                    .map(LitStr::value)
+                    .collect();
+            } else {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "Unknown #[command] option, expected permission, level or aliases",
+                ));
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+impl CommandOptions {
+    /// The `permission`, `op_level` and `aliases` fields of a `CommandDef`.
+    fn fields(&self) -> TokenStream2 {
+        let permission = match &self.permission {
+            Some(permission) => quote::quote! { Some(#permission) },
+            None => quote::quote! { None },
+        };
+
+        let op_level = match (&self.permission, self.op_level) {
+            (_, Some(level)) => level,
+            (Some(_), None) => 2,
+            (None, None) => 0,
+        };
+
+        let aliases = &self.aliases;
+
+        quote::quote! {
+            permission: #permission,
+            op_level: #op_level,
+            aliases: vec![#(#aliases),*],
+        }
+    }
+}
+
+/// The constraints of a parameter, `#[arg(min = 0, max = 10, default = 5)]`.
+#[derive(Default)]
+struct ArgOptions {
+    min: Option<syn::Expr>,
+    max: Option<syn::Expr>,
+    regex: Option<LitStr>,
+    default: Option<syn::Expr>,
+}
+
+impl Parse for ArgOptions {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut options = Self::default();
+
+        while !input.is_empty() {
+            let name: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+
+            if name == "min" {
+                options.min = Some(input.parse()?);
+            } else if name == "max" {
+                options.max = Some(input.parse()?);
+            } else if name == "regex" {
+                let regex: LitStr = input.parse()?;
+                if let Err(e) = regex::Regex::new(&regex.value()) {
+                    return Err(syn::Error::new(regex.span(), e));
+                }
+                options.regex = Some(regex);
+            } else if name == "default" {
+                options.default = Some(input.parse()?);
+            } else {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "Unknown #[arg] option, expected min, max, regex or default",
+                ));
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+impl ArgOptions {
+    /// Merges the options of all `#[arg]` attributes.
+    fn from_attrs(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut options = Self::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("arg")) {
+            let other: Self = attr.parse_args()?;
+            options.min = other.min.or(options.min);
+            options.max = other.max.or(options.max);
+            options.regex = other.regex.or(options.regex);
+            options.default = other.default.or(options.default);
+        }
+
+        Ok(options)
+    }
+
+    fn has_bounds(&self) -> bool {
+        self.min.is_some() || self.max.is_some()
+    }
+
+    /// `min` and `max` as `Option<f64>`s.
+    fn bounds(&self) -> (TokenStream2, TokenStream2) {
+        let bound = |bound: &Option<syn::Expr>| match bound {
+            Some(bound) => quote::quote! { Some((#bound) as f64) },
+            None => quote::quote! { None },
+        };
+
+        (bound(&self.min), bound(&self.max))
+    }
+}
+
+fn expand_group(input: ItemMod, options: &CommandOptions) -> syn::Result<TokenStream2> {
+    let mod_visibility = &input.vis;
+    let mod_name = &input.ident;
+    let mod_attrs = &input.attrs;
+
+    let Some((_, items)) = input.content else {
+        return Err(syn::Error::new_spanned(
+            mod_name,
+            "#[command] modules must have a body",
+        ));
+    };
+
+    let mut expanded = Vec::new();
+    let mut subcommands = Vec::new();
+    let mut overloads = Vec::new();
+    // The types the overloads run with, the same for all of them.
+    let mut overload_types = None;
+
+    for item in items {
+        match item {
+            Item::Fn(mut f) if f.attrs.iter().any(|attr| attr.path.is_ident("subcommand")) => {
+                f.attrs.retain(|attr| !attr.path.is_ident("subcommand"));
+                subcommands.push(f.sig.ident.clone());
+                expanded.push(expand_command(f, &CommandOptions::default(), false)?.0);
+            }
+            Item::Fn(mut f) if f.attrs.iter().any(|attr| attr.path.is_ident("overload")) => {
+                f.attrs.retain(|attr| !attr.path.is_ident("overload"));
+                overloads.push(f.sig.ident.clone());
+                let (command, types) = expand_command(f, &CommandOptions::default(), false)?;
+                expanded.push(command);
+                overload_types.get_or_insert(types);
+            }
+            item => expanded.push(quote::quote! { #item }),
+        }
+    }
+
+    if subcommands.is_empty() && overloads.is_empty() {
+        return Err(syn::Error::new_spanned(
+            mod_name,
+            "#[command] modules need at least one #[subcommand] or #[overload] function",
+        ));
+    }
+
+    if !subcommands.is_empty() && !overloads.is_empty() {
+        return Err(syn::Error::new_spanned(
+            mod_name,
+            "#[command] modules can have #[subcommand] or #[overload] functions, not both",
+        ));
+    }
+
+    let description = parse_doc(mod_attrs).description();
+
+    let mod_name_str = mod_name.to_string();
+    let permission_fields = options.fields();
+    let register_fn = format_ident!("{}_def", mod_name);
+
+    if let Some([game_ty, sender_ty, server_ty]) = overload_types {
+        let overload_defs = overloads.iter().map(|s| format_ident!("{}_def", s));
+
+        // The overloads are tried in the order they are written, by a
+        // function in the module so the types resolve like theirs.
+        return Ok(quote::quote! {
+            #(#mod_attrs)*
+            #mod_visibility mod #mod_name {
+                #(#expanded)*
+
+                #[doc(hidden)]
+                pub fn __run_overloads(__args: ::piquant_command::Arguments, __game: &#game_ty, __sender: &mut #sender_ty, __server: &mut #server_ty) -> Result<(), Box<dyn ::std::error::Error>> {
+                    ::piquant_command::CommandService::run_overloads(
+                        &[#(#overloads as ::piquant_command::Command<_, _, _>),*],
+                        __args,
+                        __game,
+                        __sender,
+                        __server,
+                    )
+                }
+            }
+
+            pub fn #register_fn() -> ::piquant_command::CommandDef {
+                ::piquant_command::CommandDef {
+                    name: #mod_name_str,
+                    description: #description,
+                    arguments: vec![],
+                    subcommands: vec![],
+                    overloads: vec![
+                        #(#mod_name::#overload_defs()),*
+                    ],
+                    #permission_fields
+                }
+            }
+
+            ::piquant_command::inventory::submit! {
+                ::piquant_command::Registration {
+                    name: #mod_name_str,
+                    register: |service| {
+                        let service = service.downcast_mut::<::piquant_command::CommandService<_, _, _>>()?;
+                        Some(service.add_command(#register_fn(), #mod_name::__run_overloads as ::piquant_command::Command<_, _, _>))
+                    },
+                }
+            }
+        });
+    }
+    let subcommand_defs = subcommands.iter().map(|s| format_ident!("{}_def", s));
+    let subcommand_names = subcommands.iter().map(|s| s.to_string());
+
+    Ok(quote::quote! {
+        #(#mod_attrs)*
+        #mod_visibility mod #mod_name {
+            #(#expanded)*
+        }
+
+        pub fn #register_fn() -> ::piquant_command::CommandDef {
+            ::piquant_command::CommandDef {
+                name: #mod_name_str,
+                description: #description,
+                arguments: vec![],
+                subcommands: vec![
+                    #(#mod_name::#subcommand_defs()),*
+                ],
+                overloads: vec![],
+                #permission_fields
+            }
+        }
+
+        // The service is the one the subcommands were generated for.
+        ::piquant_command::inventory::submit! {
+            ::piquant_command::Registration {
+                name: #mod_name_str,
+                register: |service| {
+                    let subcommands = vec![
+                        #((#subcommand_names, #mod_name::#subcommands as ::piquant_command::Command<_, _, _>)),*
+                    ];
+                    let service = service.downcast_mut::<::piquant_command::CommandService<_, _, _>>()?;
+                    Some(service.add_command_group(#register_fn(), subcommands))
+                },
+            }
+        }
+    })
+}
+
+/// Expands a command function, `register` is `false` for subcommands and
+/// overloads, which are registered with their group. Also returns the game,
+/// sender and server types the function is generated for.
+fn expand_command(
+    input: ItemFn,
+    options: &CommandOptions,
+    register: bool,
+) -> syn::Result<(TokenStream2, [TokenStream2; 3])> {
+    let fn_visiblity = &input.vis;
+    let fn_name = &input.sig.ident;
+    let fn_args = &input.sig.inputs;
+    let fn_ret = &input.sig.output;
+    let fn_body = &input.block;
+
+    let fn_doc = parse_doc(&input.attrs);
+
+    check_return_type(&input.sig)?;
 
     let mut arguments: Vec<TokenStream2> = Vec::new();
     let mut arg_defs: Vec<TokenStream2> = Vec::new();
     let mut client_ident = None;
     let mut world_ident = None;
-    let mut game_ident = None;
-
-    // validate the types of all arguments, the can only be i64, f64, String or bool
+    let mut game_param = None;
+    let mut sender_param = None;
+    let mut server_param = None;
+    // The game type `Client<G>`, `World<G>` or `Server<G>` are written with.
+    let mut context_game = None;
+    let mut uses_origin = false;
+
+    // validate the types of all arguments, arguments that aren't the game, sender,
+    // server, world or client are parsed with their `FromArgument` impl
     for arg in fn_args {
-        match arg {
-            syn::FnArg::Typed(t) => {
-                let ty = &t.ty;
-                match *ty.clone() {
-                    syn::Type::Path(p) => {
-                        let path = &p.path;
-
-                        if path.segments.len() != 1 {
-                            panic!("Invalid argument type, segments must have a length of 1");
-                        }
-
-                        let segment = &path.segments[0];
-
-                        let mut is_optional = false;
-
-                        let var_name = match *t.pat.clone() {
-                            syn::Pat::Ident(i) => i.ident.to_string(),
-                            _ => panic!("Invalid argument name"),
-                        };
-
-                        if segment.ident == "Game" {
-                            game_ident = Some(format_ident!("{}", var_name));
-                            continue;
-                        }
-
-                        // Client<Game> is allowed
-                        if segment.ident == "Client" {
-                            client_ident = Some(format_ident!("{}", var_name));
-                            continue;
-                        }
-
-                        if segment.ident == "World" {
-                            world_ident = Some(format_ident!("{}", var_name));
-                            continue;
-                        }
-
-                        // extract the type name as string from p
-                        let mut type_name = parse_type(&segment.ident);
-
-                        // check if it's an Option
-                        if segment.ident == "Option" {
-                            is_optional = true;
-
-                            match segment.arguments {
-                                syn::PathArguments::AngleBracketed(ref a) => {
-                                    if a.args.len() != 1 {
-                                        panic!(
-                                            "Invalid argument type, Option must have a length of 1"
-                                        );
-                                    }
-
-                                    let arg = &a.args[0];
-
-                                    match arg {
-                                        syn::GenericArgument::Type(ref t) => match t.clone() {
-                                            syn::Type::Path(p) => {
-                                                let path = &p.path;
-
-                                                if path.segments.len() != 1 {
-                                                    panic!("Invalid argument type, segments must have a length of 1");
-                                                }
-
-                                                let segment = &path.segments[0];
-
-                                                type_name = parse_type(&segment.ident);
-
-                                                if type_name == "Unknown Type" {
-                                                    panic!(
-                                                        "Invalid argument type {}",
-                                                        segment.ident
-                                                    );
-                                                }
-                                            }
-                                            _ => panic!("Invalid argument type, must be a path"),
-                                        },
-                                        _ => panic!("Invalid argument type, must be a type"),
-                                    }
-                                }
-                                _ => panic!("Invalid argument type, must be an angle bracketed"),
-                            }
-                        }
-                        if type_name == "Unknown Type" {
-                            panic!("Invalid argument type {}", segment.ident);
-                        }
-
-                        let arg_index = arguments.len() + 1;
-
-                        let mut a = quote::quote! {};
-
-                        arg_defs.push(quote::quote! {
-                            ::piquant_command::ArgumentDef {
-                                name: #var_name,
-                                ty: #type_name,
-                                optional: #is_optional,
-                            }
-                        });
-
-                        if is_optional {
-                            a.extend(quote::quote! {
-                                let #t = match __args.pop_front() {
-                                    Some(v) => match v.try_into() {
-                                        Ok(v) => Some(v),
-                                        Err(e) => return Err(format!("Invalid argument #{}: {}", #arg_index, e).into()),
-                                    },
-                                    None => None,
-                                };
-                            });
-                        } else {
-                            a.extend(quote::quote! {
-                                // args is a vec
-                                let #t = match __args.pop_front() {
-                                    Some(v) => match v.try_into() {
-                                        Ok(v) => v,
-                                        Err(e) => return Err(format!("Invalid argument #{}: {}", #arg_index, e).into()),
-                                    },
-                                    None => return Err(format!("Missing argument #{}: {}({})", #arg_index, #type_name, #var_name).into()),
-                                };
-                            });
-                        }
-
-                        arguments.push(a);
-                    }
-                    _ => panic!("Invalid argument type, must be a path"),
+        let t = match arg {
+            syn::FnArg::Typed(t) => t,
+            syn::FnArg::Receiver(receiver) => {
+                return Err(syn::Error::new_spanned(
+                    receiver,
+                    "Commands can't take self, they must be free functions",
+                ));
+            }
+        };
+
+        let mut literals = Vec::new();
+        for attr in t.attrs.iter().filter(|attr| attr.path.is_ident("literal")) {
+            let words = attr
+                .parse_args_with(Punctuated::<LitStr, Token![,]>::parse_terminated)
+                .map_err(|e| {
+                    syn::Error::new(
+                        e.span(),
+                        "Invalid literal attribute, expected #[literal(\"a\", \"b\")]",
+                    )
+                })?;
+            literals.extend(words.iter().map(LitStr::value));
+        }
+
+        let suggestions = match t.attrs.iter().find(|attr| attr.path.is_ident("suggest")) {
+            Some(attr) => {
+                let provider: LitStr = attr.parse_args().map_err(|e| {
+                    syn::Error::new(
+                        e.span(),
+                        "Invalid suggest attribute, expected #[suggest(\"name\")]",
+                    )
+                })?;
+                let provider = provider.value();
+                quote::quote! { Some(#provider) }
+            }
+            None => quote::quote! { None },
+        };
+
+        let arg_options = ArgOptions::from_attrs(&t.attrs)?;
+        let context = context_role(&t.attrs)?;
+
+        let mut t = t.clone();
+        t.attrs.clear();
+
+        let ty = &t.ty;
+        let Some(segment) = last_segment(ty) else {
+            return Err(syn::Error::new_spanned(
+                ty,
+                "Unsupported argument type, expected a type implementing FromArgument",
+            ));
+        };
+
+        let var_ident = match &*t.pat {
+            syn::Pat::Ident(i) => i.ident.clone(),
+            pat => {
+                return Err(syn::Error::new_spanned(
+                    pat,
+                    "Command arguments must be plain names",
+                ));
+            }
+        };
+        let var_name = var_ident.to_string();
+
+        if let Some(role) = context {
+            // `Client<G>`, `World<G>` and `Server<G>` tell the game type.
+            if matches!(
+                role,
+                ContextRole::Server | ContextRole::World | ContextRole::Client
+            ) {
+                if let Some(game) = type_argument(segment) {
+                    context_game.get_or_insert_with(|| game.clone());
                 }
             }
-            _ => panic!("'self' fns not supported."),
+
+            let taken = match role {
+                ContextRole::Game => game_param.replace((var_ident, ty.clone())).is_some(),
+                ContextRole::Sender => sender_param.replace((var_ident, ty.clone())).is_some(),
+                ContextRole::Server => server_param.replace((var_ident, ty.clone())).is_some(),
+                ContextRole::World => world_ident.replace(var_ident).is_some(),
+                ContextRole::Client => client_ident.replace(var_ident).is_some(),
+            };
+
+            if taken {
+                return Err(syn::Error::new_spanned(
+                    t,
+                    "Commands can take each part of their context only once",
+                ));
+            }
+
+            continue;
         }
-    }
 
-    let game_ident = game_ident.unwrap_or(format_ident!("__game"));
-    let client_ident = client_ident.unwrap_or(format_ident!("__client"));
-    let world_ident = world_ident.unwrap_or(format_ident!("__world"));
+        // Everything else is parsed with its `FromArgument` impl, `Option`s are
+        // optional arguments.
+        let (inner, is_optional) = match option_inner(segment)? {
+            Some(inner) => (inner, true),
+            None => (*ty.clone(), false),
+        };
+        let from_argument = quote::quote! { <#inner as ::piquant_command::FromArgument> };
+
+        let is_string = last_segment(&inner).is_some_and(|segment| segment.ident == "String");
+        if !literals.is_empty() && !is_string {
+            return Err(syn::Error::new_spanned(
+                ty,
+                "Only String arguments can be literals",
+            ));
+        }
 
-    let register_fn = format_ident!("{}_def", fn_name);
+        if let (Some(default), true) = (&arg_options.default, is_optional) {
+            return Err(syn::Error::new_spanned(
+                default,
+                "Option arguments are optional already, they can't have a default",
+            ));
+        }
 
-    let description = fn_doc.first().map(|s| s.to_string());
+        let (min, max) = arg_options.bounds();
+        let bounded = quote::quote! { <#inner as ::piquant_command::Bounded> };
+
+        let parser = if arg_options.has_bounds() {
+            quote::quote! { #bounded::bounded_parser(#min, #max) }
+        } else {
+            quote::quote! { #from_argument::parser() }
+        };
+
+        let optional = is_optional || arg_options.default.is_some();
+
+        let argument_description = fn_doc.argument(&var_name);
+        arg_defs.push(quote::quote! {
+            ::piquant_command::ArgumentDef {
+                name: #var_name,
+                ty: #from_argument::NAME,
+                parser: #parser,
+                words: #from_argument::WORDS,
+                optional: #optional,
+                literals: match vec![#(#literals),*] {
+                    literals if literals.is_empty() => #from_argument::literals(),
+                    literals => literals,
+                },
+                other_words: #from_argument::OTHER_WORDS,
+                suggestions: #suggestions.or(#from_argument::SUGGESTIONS),
+                description: #argument_description,
+            }
+        });
+
+        uses_origin = true;
+
+        let (value, missing) = if is_optional {
+            (quote::quote! { Some(v) }, quote::quote! { None })
+        } else if let Some(default) = &arg_options.default {
+            (
+                quote::quote! { v },
+                quote::quote! { ::std::convert::Into::into(#default) },
+            )
+        } else {
+            (
+                quote::quote! { v },
+                quote::quote! { return Err(::piquant_command::CommandError::missing_argument(#var_name, #from_argument::NAME, __args.end()).into()) },
+            )
+        };
+
+        // The constraints of `#[arg]`, checked before the argument is used.
+        let mut checks = TokenStream2::new();
+
+        if arg_options.has_bounds() {
+            checks.extend(quote::quote! {
+                if let Err(e) = #bounded::check_bounds(&v, #min, #max) {
+                    return Err(::piquant_command::CommandError::invalid_argument(#var_name, #from_argument::NAME, e, __args.span()).into());
+                }
+            });
+        }
 
-    let fn_name_str = fn_name.to_string();
+        if let Some(regex) = &arg_options.regex {
+            checks.extend(quote::quote! {
+                static __REGEX: ::std::sync::OnceLock<::piquant_command::regex::Regex> = ::std::sync::OnceLock::new();
+                let __regex = __REGEX.get_or_init(|| ::piquant_command::regex::Regex::new(#regex).unwrap());
 
-    let q = if description.is_some() {
+                let __value: &str = ::std::convert::AsRef::as_ref(&v);
+                if !__regex.is_match(__value) {
+                    return Err(::piquant_command::CommandError::invalid_argument(#var_name, #from_argument::NAME, format!("'{}' does not match {}", __value, #regex), __args.span()).into());
+                }
+            });
+        }
+
+        let mut a = quote::quote! {
+            let #t = if __args.is_empty() {
+                #missing
+            } else {
+                let __start = __args.remaining().map_or(0, |span| span.start);
+                match #from_argument::from_argument(&mut __args, &__origin) {
+                    Ok(v) => {
+                        #checks
+                        #value
+                    }
+                    // Syntax errors already point at the right place.
+                    Err(e) if e.is::<::piquant_command::CommandError>() => return Err(e),
+                    Err(e) => return Err(::piquant_command::CommandError::invalid_argument(#var_name, #from_argument::NAME, e, __start..__args.span().end).into()),
+                }
+            };
+        };
+
+        if !literals.is_empty() {
+            let arg_ident = &t.pat;
+            let expected = format!("one of {}", literals.join(", "));
+            let value = if is_optional {
+                quote::quote! { #arg_ident.as_deref() }
+            } else {
+                quote::quote! { Some(#arg_ident.as_str()) }
+            };
+
+            a.extend(quote::quote! {
+                if let Some(v) = #value {
+                    if ![#(#literals),*].contains(&v) {
+                        return Err(::piquant_command::CommandError::invalid_argument(#var_name, #expected, format!("got {v}"), __args.span()).into());
+                    }
+                }
+            });
+        }
+
+        arguments.push(a);
+    }
+
+    // The generated function takes the types as written, so they can be fully
+    // qualified. The ones the command doesn't take come from the
+    // `CommandContext` of the ones it does.
+    let context_ty = match (&game_param, &context_game, &sender_param) {
+        (Some((_, ty)), ..) | (None, None, Some((_, ty))) => &**ty,
+        (None, Some(ty), _) => ty,
+        (None, None, None) => {
+            return Err(syn::Error::new_spanned(
+                fn_name,
+                "Commands must take the game, sender, server, world or client, marked with #[context(..)], which tell the types they run with",
+            ));
+        }
+    };
+    let context = quote::quote! { <#context_ty as ::piquant_command::CommandContext> };
+
+    let (game_ident, game_ty) = match (&game_param, &context_game) {
+        (Some((ident, ty)), _) => (ident.clone(), quote::quote! { #ty }),
+        (None, Some(ty)) => (format_ident!("__game"), quote::quote! { #ty }),
+        (None, None) => (format_ident!("__game"), quote::quote! { #context::Game }),
+    };
+    let (sender_ident, sender_ty) = match sender_param {
+        Some((ident, ty)) => (ident, quote::quote! { #ty }),
+        None => (
+            format_ident!("__sender"),
+            quote::quote! { #context::Sender },
+        ),
+    };
+    let server_ty = match &server_param {
+        Some((_, ty)) => quote::quote! { #ty },
+        None => quote::quote! { #context::Server },
+    };
+
+    if let Some((server_ident, _)) = &server_param {
+        if world_ident.is_some() || client_ident.is_some() {
+            return Err(syn::Error::new_spanned(
+                server_ident,
+                "A command taking the Server can't take a World or Client as well",
+            ));
+        }
+    }
+
+    // Positions are relative to the sender, senders without a position are at
+    // the origin.
+    let origin = if uses_origin {
         quote::quote! {
-            Some(#description.to_string())
+            let __origin = ::piquant_command::Origin {
+                position: #sender_ident.position().unwrap_or([0.0; 3]),
+                yaw: #sender_ident.rotation().map_or(0.0, |r| r.0),
+                pitch: #sender_ident.rotation().map_or(0.0, |r| r.1),
+                eye_height: #sender_ident.eye_height(),
+            };
         }
     } else {
+        quote::quote! {}
+    };
+
+    // The world of the sender, or the first world if it has none.
+    let world = match &world_ident {
+        Some(world_ident) => quote::quote! {
+            let __world_id = match #sender_ident.world() {
+                Some(id) => id,
+                None => __server.worlds.iter().next().map(|(id, _)| id).ok_or("There is no world")?,
+            };
+            let #world_ident = __server.worlds.get_mut(__world_id).ok_or("The world no longer exists")?;
+        },
+        None => quote::quote! {},
+    };
+
+    let server = match &server_param {
+        Some((server_ident, _)) => quote::quote! {
+            let #server_ident = __server;
+        },
+        None => quote::quote! {},
+    };
+
+    let client = match &client_ident {
+        Some(client_ident) => quote::quote! {
+            let #client_ident = #sender_ident
+                .client_id()
+                .and_then(|id| __server.clients.get_mut(id))
+                .ok_or("Only players can run this command")?;
+        },
+        None => quote::quote! {},
+    };
+
+    let register_fn = format_ident!("{}_def", fn_name);
+
+    let fn_name_str = fn_name.to_string();
+    let permission_fields = options.fields();
+
+    let description = fn_doc.description();
+
+    let registration = if register {
         quote::quote! {
-            None
+            ::piquant_command::inventory::submit! {
+                ::piquant_command::Registration {
+                    name: #fn_name_str,
+                    register: |service| {
+                        let service = service.downcast_mut::<::piquant_command::CommandService<#game_ty, #sender_ty, #server_ty>>()?;
+                        Some(service.add_command(#register_fn(), #fn_name))
+                    },
+                }
+            }
         }
+    } else {
+        quote::quote! {}
     };
 
-    quote::quote! {
-        #fn_visiblity fn #fn_name(mut __args: ::piquant_command::Arguments, #game_ident: &Game, #client_ident: &mut Client<Game>, #world_ident: &mut World<Game>) #fn_ret {
+    let command = quote::quote! {
+        #fn_visiblity fn #fn_name(mut __args: ::piquant_command::Arguments, #game_ident: &#game_ty, #sender_ident: &mut #sender_ty, __server: &mut #server_ty) #fn_ret {
+            #origin
             #(#arguments)*
+            if let Some(__span) = __args.remaining() {
+                return Err(::piquant_command::CommandError::syntax("Incorrect argument for command", __span.start).into());
+            }
+            #world
+            #client
+            #server
 
             #fn_body
         }
@@ -221,12 +906,65 @@ pub fn command(_: TokenStream, input: TokenStream) -> TokenStream {
         pub fn #register_fn() -> ::piquant_command::CommandDef {
             ::piquant_command::CommandDef {
                 name: #fn_name_str,
-                description: #q,
+                description: #description,
                 arguments: vec![
                     #(#arg_defs),*
                 ],
+                subcommands: vec![],
+                overloads: vec![],
+                #permission_fields
             }
         }
+
+        #registration
+    };
+
+    Ok((command, [game_ty, sender_ty, server_ty]))
+}
+
+/// The parts of the context a command can take instead of an argument.
+#[derive(Clone, Copy)]
+enum ContextRole {
+    Game,
+    Sender,
+    Server,
+    World,
+    Client,
+}
+
+/// The role of a parameter marked with `#[context(sender)]` and the like, or
+/// `None` for arguments.
+fn context_role(attrs: &[syn::Attribute]) -> syn::Result<Option<ContextRole>> {
+    let Some(attr) = attrs.iter().find(|attr| attr.path.is_ident("context")) else {
+        return Ok(None);
+    };
+
+    let message = "Invalid context attribute, expected #[context(game)], #[context(sender)], #[context(server)], #[context(world)] or #[context(client)]";
+    let role: Ident = attr
+        .parse_args()
+        .map_err(|e| syn::Error::new(e.span(), message))?;
+
+    let role = match role.to_string().as_str() {
+        "game" => ContextRole::Game,
+        "sender" => ContextRole::Sender,
+        "server" => ContextRole::Server,
+        "world" => ContextRole::World,
+        "client" => ContextRole::Client,
+        _ => return Err(syn::Error::new_spanned(role, message)),
+    };
+
+    Ok(Some(role))
+}
+
+/// Commands return errors to their sender, so they must return a `Result`.
+fn check_return_type(sig: &syn::Signature) -> syn::Result<()> {
+    let message = "Commands must return a Result, e.g. Result<(), Box<dyn std::error::Error>>";
+
+    match &sig.output {
+        syn::ReturnType::Default => Err(syn::Error::new_spanned(&sig.ident, message)),
+        syn::ReturnType::Type(_, ty) => match last_segment(ty) {
+            Some(segment) if segment.ident == "Result" => Ok(()),
+            _ => Err(syn::Error::new_spanned(ty, message)),
+        },
     }
-    .into()
 }
@@ -0,0 +1,9 @@
+use piquant_macros::FromArgument;
+
+#[derive(FromArgument)]
+enum Mode {
+    Fast,
+    Slow(u32),
+}
+
+fn main() {}
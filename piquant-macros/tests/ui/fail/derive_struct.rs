@@ -0,0 +1,6 @@
+use piquant_macros::FromArgument;
+
+#[derive(FromArgument)]
+struct Mode;
+
+fn main() {}
@@ -0,0 +1,8 @@
+use piquant_macros::command;
+
+#[command]
+mod group {
+    pub fn helper() {}
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use piquant_macros::command;
+
+#[command]
+fn test(#[context(player)] player: Player) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
+
+fn main() {}
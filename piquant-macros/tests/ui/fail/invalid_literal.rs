@@ -0,0 +1,8 @@
+use piquant_macros::command;
+
+#[command]
+fn test(#[literal(1, 2)] value: String) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
+
+fn main() {}
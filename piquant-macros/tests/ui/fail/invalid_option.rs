@@ -0,0 +1,8 @@
+use piquant_macros::command;
+
+#[command]
+fn test(value: Option<i64, i64>) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
+
+fn main() {}
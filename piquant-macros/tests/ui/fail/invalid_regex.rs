@@ -0,0 +1,8 @@
+use piquant_macros::command;
+
+#[command]
+fn test(#[arg(regex = "[a-z")] name: String) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use piquant_macros::command;
+
+#[command]
+fn test(#[literal("a", "b")] value: i64) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use piquant_macros::command;
+
+#[command]
+fn test(value: i64) {}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use piquant_macros::command;
+
+#[command]
+mod group {
+    #[subcommand]
+    pub fn set(#[context(sender)] _sender: CommandSender, value: i64) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    #[overload]
+    pub fn number(#[context(sender)] _sender: CommandSender, value: i64) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+}
+
+fn main() {}
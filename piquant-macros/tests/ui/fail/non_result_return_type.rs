@@ -0,0 +1,8 @@
+use piquant_macros::command;
+
+#[command]
+fn test(value: i64) -> i64 {
+    value
+}
+
+fn main() {}
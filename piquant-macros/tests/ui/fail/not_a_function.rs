@@ -0,0 +1,6 @@
+use piquant_macros::command;
+
+#[command]
+struct Test;
+
+fn main() {}
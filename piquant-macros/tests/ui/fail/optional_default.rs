@@ -0,0 +1,8 @@
+use piquant_macros::command;
+
+#[command]
+fn test(#[arg(default = 5)] count: Option<i64>) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
+
+fn main() {}
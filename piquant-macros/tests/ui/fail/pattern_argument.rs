@@ -0,0 +1,8 @@
+use piquant_macros::command;
+
+#[command]
+fn test(_: i64) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use piquant_macros::command;
+
+struct Commands;
+
+impl Commands {
+    #[command]
+    fn test(&self) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use piquant_macros::command;
+
+#[command]
+fn test(
+    #[context(server)] server: Server<Game>,
+    #[context(world)] world: World<Game>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use piquant_macros::command;
+
+#[command]
+fn test(#[arg(min = 0, step = 2)] count: i64) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
+
+fn main() {}
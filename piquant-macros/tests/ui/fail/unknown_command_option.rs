@@ -0,0 +1,8 @@
+use piquant_macros::command;
+
+#[command(permission = "piquant.command.test", name = "other")]
+fn test() -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
+
+fn main() {}
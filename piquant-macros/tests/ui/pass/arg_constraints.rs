@@ -0,0 +1,68 @@
+use piquant_command::{parse, CommandError};
+use piquant_macros::command;
+
+pub struct Game;
+
+pub struct CommandSender;
+
+impl CommandSender {
+    pub fn position(&self) -> Option<[f64; 3]> {
+        None
+    }
+
+    pub fn rotation(&self) -> Option<(f32, f32)> {
+        None
+    }
+
+    pub fn eye_height(&self) -> f64 {
+        0.0
+    }
+}
+
+pub struct Server<G>(std::marker::PhantomData<G>);
+
+impl piquant_command::CommandContext for CommandSender {
+    type Game = Game;
+    type Sender = CommandSender;
+    type Server = Server<Game>;
+}
+
+#[command]
+fn height(
+    #[context(sender)] sender: CommandSender,
+    #[arg(min = -64, max = 319)] y: i64,
+    #[arg(regex = "^[a-z]+$", default = "stone")] block: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let _ = sender;
+    assert!((-64..=319).contains(&y));
+    assert!(block.chars().all(|c| c.is_ascii_lowercase()));
+    Ok(())
+}
+
+fn run(input: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let (_, args) = parse(input)?;
+    height(
+        args,
+        &Game,
+        &mut CommandSender,
+        &mut Server(Default::default()),
+    )
+}
+
+fn main() {
+    run("/height 100").unwrap();
+    run("/height -64 dirt").unwrap();
+
+    for input in ["/height 320", "/height -65", "/height 0 Dirt"] {
+        let error = run(input).unwrap_err();
+        assert!(error.is::<CommandError>(), "{input}: {error}");
+    }
+
+    match height_def().arguments[0].parser {
+        piquant_command::Parser::Long { min, max } => {
+            assert_eq!((min, max), (Some(-64), Some(319)))
+        }
+        ref parser => panic!("unexpected parser {parser:?}"),
+    }
+    assert!(height_def().arguments[1].optional);
+}
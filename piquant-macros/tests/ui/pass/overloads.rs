@@ -0,0 +1,80 @@
+use piquant_command::{parse, CommandError};
+use piquant_macros::command;
+
+pub struct Game;
+
+#[derive(Default)]
+pub struct CommandSender {
+    ran: Option<&'static str>,
+}
+
+impl CommandSender {
+    pub fn position(&self) -> Option<[f64; 3]> {
+        None
+    }
+
+    pub fn rotation(&self) -> Option<(f32, f32)> {
+        None
+    }
+
+    pub fn eye_height(&self) -> f64 {
+        0.0
+    }
+}
+
+pub struct Server<G>(std::marker::PhantomData<G>);
+
+impl piquant_command::CommandContext for CommandSender {
+    type Game = Game;
+    type Sender = CommandSender;
+    type Server = Server<Game>;
+}
+
+/// Overloads are tried in order, the first one whose arguments parse runs.
+#[command]
+mod pick {
+    use super::*;
+
+    #[overload]
+    pub fn number(
+        #[context(sender)] sender: CommandSender,
+        _value: i64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        sender.ran = Some("number");
+        Ok(())
+    }
+
+    #[overload]
+    pub fn word(
+        #[context(sender)] sender: CommandSender,
+        _value: String,
+        _count: i64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        sender.ran = Some("word");
+        Ok(())
+    }
+}
+
+fn run(input: &str) -> Result<Option<&'static str>, Box<dyn std::error::Error>> {
+    let (_, args) = parse(input)?;
+    let mut sender = CommandSender::default();
+    pick::__run_overloads(args, &Game, &mut sender, &mut Server(Default::default()))?;
+    Ok(sender.ran)
+}
+
+fn main() {
+    assert_eq!(run("/pick 5").unwrap(), Some("number"));
+    assert_eq!(run("/pick abc 2").unwrap(), Some("word"));
+    assert_eq!(run("/pick 5 6").unwrap(), Some("word"));
+
+    // The error of the overload that got furthest is reported.
+    let error = run("/pick abc").unwrap_err();
+    match error.downcast_ref::<CommandError>() {
+        Some(CommandError::Syntax { message, .. }) => assert!(message.contains("_count")),
+        _ => panic!("unexpected error {error}"),
+    }
+
+    let def = pick_def();
+    assert_eq!(def.overloads.len(), 2);
+    assert!(def.arguments.is_empty() && def.subcommands.is_empty());
+}
@@ -0,0 +1,86 @@
+mod game {
+    pub struct Game;
+
+    pub struct CommandSender;
+
+    pub type Sender = CommandSender;
+
+    impl CommandSender {
+        pub fn position(&self) -> Option<[f64; 3]> {
+            None
+        }
+
+        pub fn rotation(&self) -> Option<(f32, f32)> {
+            None
+        }
+
+        pub fn eye_height(&self) -> f64 {
+            0.0
+        }
+    }
+
+    pub struct Server<G>(pub std::marker::PhantomData<G>);
+
+    impl piquant_command::CommandContext for Game {
+        type Game = Game;
+        type Sender = CommandSender;
+        type Server = Server<Game>;
+    }
+
+    impl piquant_command::CommandContext for CommandSender {
+        type Game = Game;
+        type Sender = CommandSender;
+        type Server = Server<Game>;
+    }
+}
+
+/// Commands can spell out the paths of their parameter and return types.
+#[piquant_macros::command]
+fn test(
+    #[context(game)] _game: self::game::Game,
+    #[context(sender)] _sender: crate::game::CommandSender,
+    #[context(server)] _server: game::Server<game::Game>,
+    value: std::string::String,
+    count: std::option::Option<i64>,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    let _ = (value, count);
+    Ok(())
+}
+
+/// The types a command doesn't take don't have to be in scope, and the ones
+/// it takes can have any name.
+#[piquant_macros::command]
+fn sender_only(
+    #[context(sender)] _sender: game::Sender,
+    value: std::string::String,
+) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+    let _ = value;
+    Ok(())
+}
+
+#[piquant_macros::command]
+mod group {
+    /// Subcommands find the types of their group the same way.
+    #[subcommand]
+    pub fn server_only(
+        #[context(server)] _server: super::game::Server<super::game::Game>,
+    ) -> std::result::Result<(), std::boxed::Box<dyn std::error::Error>> {
+        Ok(())
+    }
+}
+
+fn main() {
+    let def = test_def();
+    assert_eq!(def.arguments.len(), 2);
+
+    let (_, args) = piquant_command::parse("/sender_only hello").unwrap();
+    sender_only(
+        args,
+        &game::Game,
+        &mut game::CommandSender,
+        &mut game::Server(Default::default()),
+    )
+    .unwrap();
+
+    assert_eq!(group_def().subcommands.len(), 1);
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use valence::{
+    prelude::{Chunk, ChunkPos},
+    protocol::BlockState,
+};
+use valence_nbt::{compound, Compound, List, Value};
+
+use crate::light::{ChunkLight, LightKind};
+
+/// The data version of Minecraft 1.19.3.
+const DATA_VERSION: i32 = 3218;
+
+const BLOCKS_PER_SECTION: usize = 16 * 16 * 16;
+
+/// Converts a chunk into the NBT of an Anvil chunk, the inverse of
+/// `valence_anvil::to_valence`. Only the blocks and light are replaced in
+/// `original`, the NBT the chunk was last saved with, so block entities,
+/// entities, heightmaps and biomes piquant doesn't track are kept. Sections
+/// without saved biomes are saved as plains.
+pub fn to_anvil<C: Chunk>(
+    chunk: &C,
+    light: &ChunkLight,
+    pos: ChunkPos,
+    sect_offset: i32,
+    mut original: Compound,
+) -> Compound {
+    let mut biomes = original_biomes(&mut original);
+    let mut sections = Vec::with_capacity(chunk.section_count());
+
+    for sect_y in 0..chunk.section_count() {
+        let mut palette = Vec::new();
+        let mut palette_idxs = HashMap::new();
+        let mut idxs = Vec::with_capacity(BLOCKS_PER_SECTION);
+
+        for i in 0..BLOCKS_PER_SECTION {
+            let (x, z, y) = (i % 16, i / 16 % 16, i / (16 * 16));
+            let block = chunk.block_state(x, sect_y * 16 + y, z);
+
+            let idx = *palette_idxs.entry(block).or_insert_with(|| {
+                palette.push(block_to_nbt(block));
+                palette.len() - 1
+            });
+
+            idxs.push(idx as u64);
+        }
+
+        let mut block_states = compound! {
+            "palette" => List::Compound(palette),
+        };
+
+        if palette_idxs.len() > 1 {
+            let bits_per_idx = bit_width(palette_idxs.len() - 1).max(4);
+            block_states.insert("data", pack(&idxs, bits_per_idx));
+        }
+
+        let y = (sect_y as i32 - sect_offset) as i8;
+
+        let mut section = compound! {
+            "Y" => y,
+            "block_states" => block_states,
+            "biomes" => biomes.remove(&y).unwrap_or_else(|| compound! {
+                "palette" => List::String(vec!["minecraft:plains".into()]),
+            }),
+        };
+
+        if !light.is_empty() {
+            section.insert("SkyLight", nibbles(light.section(LightKind::Sky, sect_y)));
+            section.insert(
+                "BlockLight",
+                nibbles(light.section(LightKind::Block, sect_y)),
+            );
+        }
+
+        sections.push(section);
+    }
+
+    original.insert("DataVersion", DATA_VERSION);
+    original.insert("xPos", pos.x);
+    original.insert("zPos", pos.z);
+    original.insert("yPos", -sect_offset);
+    original.insert("Status", "full");
+    original.insert("isLightOn", !light.is_empty());
+    original.insert("sections", List::Compound(sections));
+
+    if !original.contains_key("LastUpdate") {
+        original.insert("LastUpdate", 0_i64);
+    }
+
+    original
+}
+
+/// Takes the sections out of a saved chunk and returns their biomes by
+/// section Y.
+fn original_biomes(original: &mut Compound) -> HashMap<i8, Compound> {
+    let Some(Value::List(List::Compound(sections))) = original.remove("sections") else {
+        return HashMap::new();
+    };
+
+    sections
+        .into_iter()
+        .filter_map(|mut section| {
+            let Some(Value::Byte(y)) = section.get("Y") else {
+                return None;
+            };
+            let y = *y;
+
+            match section.remove("biomes") {
+                Some(Value::Compound(biomes)) => Some((y, biomes)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn block_to_nbt(block: BlockState) -> Compound {
+    let kind = block.to_kind();
+
+    let mut nbt = compound! {
+        "Name" => format!("minecraft:{}", kind.to_str()),
+    };
+
+    let properties: Compound = kind
+        .props()
+        .iter()
+        .filter_map(|name| {
+            let value = block.get(*name)?;
+            Some((name.to_str().to_string(), Value::from(value.to_str())))
+        })
+        .collect();
+
+    if !properties.is_empty() {
+        nbt.insert("Properties", properties);
+    }
+
+    nbt
+}
+
+/// Packs palette indices into longs the way Anvil expects them, without
+/// indices spanning two longs.
+fn pack(idxs: &[u64], bits_per_idx: usize) -> Vec<i64> {
+    let idxs_per_long = 64 / bits_per_idx;
+
+    idxs.chunks(idxs_per_long)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0_u64, |long, (j, idx)| long | idx << (bits_per_idx * j)) as i64
+        })
+        .collect()
+}
+
+fn nibbles(section: &[u8]) -> Vec<i8> {
+    section.iter().map(|b| *b as i8).collect()
+}
+
+const fn bit_width(n: usize) -> usize {
+    (usize::BITS - n.leading_zeros()) as _
+}
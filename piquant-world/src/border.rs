@@ -0,0 +1,261 @@
+use valence::{
+    prelude::{ChunkPos, Client, Config, Vec3},
+    protocol::{packets::s2c::play::WorldBorderInitialize, VarInt, VarLong},
+};
+
+use crate::packets::{
+    SetBorderCenter, SetBorderLerpSize, SetBorderSize, SetBorderWarningDelay,
+    SetBorderWarningDistance,
+};
+
+/// The largest diameter the client accepts.
+pub const MAX_BORDER_DIAMETER: f64 = 59_999_968.0;
+
+/// The furthest the center of a world border can be from the origin.
+pub const MAX_BORDER_CENTER: f64 = 29_999_984.0;
+
+/// Nether portals never put a player further out than this.
+const PORTAL_TELEPORT_BOUNDARY: i32 = 29_999_984;
+
+const MILLIS_PER_TICK: i64 = 50;
+
+/// The square a world is confined to. The diameter can move to a new value
+/// over a number of ticks.
+#[derive(Clone, Debug)]
+pub struct WorldBorder {
+    center_x: f64,
+    center_z: f64,
+    old_diameter: f64,
+    new_diameter: f64,
+    /// The length of the current resize and how far along it is.
+    lerp_ticks: i64,
+    lerp_elapsed: i64,
+
+    /// Damage per second per block a player is beyond the buffer.
+    pub damage_per_block: f64,
+    /// How far outside the border a player can be without taking damage.
+    pub damage_buffer: f64,
+    warning_blocks: i32,
+    warning_time: i32,
+
+    changes: BorderChanges,
+    /// Whether the area inside the border changed since the chunks were last
+    /// checked against it.
+    bounds_changed: bool,
+}
+
+/// The parts of a border that changed since the last sync.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BorderChanges {
+    pub center: bool,
+    pub size: bool,
+    pub warning_blocks: bool,
+    pub warning_time: bool,
+}
+
+impl Default for WorldBorder {
+    fn default() -> Self {
+        Self::new(0.0, 0.0, MAX_BORDER_DIAMETER)
+    }
+}
+
+impl WorldBorder {
+    pub fn new(center_x: f64, center_z: f64, diameter: f64) -> Self {
+        let diameter = diameter.clamp(1.0, MAX_BORDER_DIAMETER);
+
+        Self {
+            center_x,
+            center_z,
+            old_diameter: diameter,
+            new_diameter: diameter,
+            lerp_ticks: 0,
+            lerp_elapsed: 0,
+            damage_per_block: 0.2,
+            damage_buffer: 5.0,
+            warning_blocks: 5,
+            warning_time: 15,
+            changes: BorderChanges::default(),
+            bounds_changed: true,
+        }
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        (self.center_x, self.center_z)
+    }
+
+    pub fn set_center(&mut self, x: f64, z: f64) {
+        self.center_x = x;
+        self.center_z = z;
+        self.changes.center = true;
+        self.bounds_changed = true;
+    }
+
+    /// The current diameter, somewhere between the old and new diameter while
+    /// the border is resizing.
+    pub fn diameter(&self) -> f64 {
+        if self.lerp_elapsed >= self.lerp_ticks {
+            return self.new_diameter;
+        }
+
+        let t = self.lerp_elapsed as f64 / self.lerp_ticks as f64;
+        self.old_diameter + (self.new_diameter - self.old_diameter) * t
+    }
+
+    /// The diameter the border is moving to.
+    pub fn target_diameter(&self) -> f64 {
+        self.new_diameter
+    }
+
+    pub fn is_resizing(&self) -> bool {
+        self.lerp_elapsed < self.lerp_ticks
+    }
+
+    /// Resizes the border over `ticks` ticks, or at once if `ticks` is 0.
+    pub fn set_diameter(&mut self, diameter: f64, ticks: i64) {
+        self.old_diameter = self.diameter();
+        self.new_diameter = diameter.clamp(1.0, MAX_BORDER_DIAMETER);
+        self.lerp_ticks = ticks.max(0);
+        self.lerp_elapsed = 0;
+        self.changes.size = true;
+        self.bounds_changed = true;
+    }
+
+    pub fn warning_blocks(&self) -> i32 {
+        self.warning_blocks
+    }
+
+    pub fn set_warning_blocks(&mut self, blocks: i32) {
+        self.warning_blocks = blocks;
+        self.changes.warning_blocks = true;
+    }
+
+    /// The warning time in seconds.
+    pub fn warning_time(&self) -> i32 {
+        self.warning_time
+    }
+
+    pub fn set_warning_time(&mut self, seconds: i32) {
+        self.warning_time = seconds;
+        self.changes.warning_time = true;
+    }
+
+    /// The smallest and largest x and z coordinates inside the border.
+    pub fn bounds(&self) -> ([f64; 2], [f64; 2]) {
+        let radius = self.diameter() / 2.0;
+
+        (
+            [self.center_x - radius, self.center_z - radius],
+            [self.center_x + radius, self.center_z + radius],
+        )
+    }
+
+    pub fn contains(&self, x: f64, z: f64) -> bool {
+        let (min, max) = self.bounds();
+
+        x >= min[0] && x < max[0] && z >= min[1] && z < max[1]
+    }
+
+    /// Returns `true` if any part of the chunk is inside the border.
+    pub fn contains_chunk(&self, pos: ChunkPos) -> bool {
+        let (min, max) = self.bounds();
+        let (x, z) = (pos.x as f64 * 16.0, pos.z as f64 * 16.0);
+
+        x + 16.0 > min[0] && x < max[0] && z + 16.0 > min[1] && z < max[1]
+    }
+
+    /// How far outside the border a position is. Negative inside the border.
+    pub fn distance_outside(&self, x: f64, z: f64) -> f64 {
+        let (min, max) = self.bounds();
+
+        let dx = (min[0] - x).max(x - max[0]);
+        let dz = (min[1] - z).max(z - max[1]);
+
+        dx.max(dz)
+    }
+
+    /// Moves a position onto the closest point inside the border.
+    pub fn clamp(&self, position: Vec3<f64>) -> Vec3<f64> {
+        let (min, max) = self.bounds();
+
+        Vec3::new(
+            position.x.clamp(min[0], max[0] - 0.5),
+            position.y,
+            position.z.clamp(min[1], max[1] - 0.5),
+        )
+    }
+
+    pub(crate) fn tick(&mut self) {
+        if self.is_resizing() {
+            self.lerp_elapsed += 1;
+            self.bounds_changed = true;
+        }
+    }
+
+    pub(crate) fn take_bounds_changed(&mut self) -> bool {
+        std::mem::take(&mut self.bounds_changed)
+    }
+
+    /// Returns what clients have to be told since the last call.
+    pub fn take_changes(&mut self) -> BorderChanges {
+        std::mem::take(&mut self.changes)
+    }
+
+    /// Sends the whole border to a client that just joined.
+    pub fn sync<G: Config>(&self, client: &mut Client<G>) {
+        client.queue_packet(&WorldBorderInitialize {
+            x: self.center_x,
+            z: self.center_z,
+            old_diameter: self.diameter(),
+            new_diameter: self.new_diameter,
+            speed: VarLong(self.remaining_millis()),
+            portal_teleport_boundary: VarInt(PORTAL_TELEPORT_BOUNDARY),
+            warning_blocks: VarInt(self.warning_blocks),
+            warning_time: VarInt(self.warning_time),
+        });
+    }
+
+    fn remaining_millis(&self) -> i64 {
+        (self.lerp_ticks - self.lerp_elapsed).max(0) * MILLIS_PER_TICK
+    }
+}
+
+impl BorderChanges {
+    pub fn is_empty(&self) -> bool {
+        !(self.center || self.size || self.warning_blocks || self.warning_time)
+    }
+
+    pub fn sync<G: Config>(&self, border: &WorldBorder, client: &mut Client<G>) {
+        if self.center {
+            client.queue_packet(&SetBorderCenter {
+                x: border.center_x,
+                z: border.center_z,
+            });
+        }
+
+        if self.size {
+            if border.is_resizing() {
+                client.queue_packet(&SetBorderLerpSize {
+                    old_diameter: border.diameter(),
+                    new_diameter: border.new_diameter,
+                    speed: VarLong(border.remaining_millis()),
+                });
+            } else {
+                client.queue_packet(&SetBorderSize {
+                    diameter: border.new_diameter,
+                });
+            }
+        }
+
+        if self.warning_blocks {
+            client.queue_packet(&SetBorderWarningDistance {
+                warning_blocks: VarInt(border.warning_blocks),
+            });
+        }
+
+        if self.warning_time {
+            client.queue_packet(&SetBorderWarningDelay {
+                warning_time: VarInt(border.warning_time),
+            });
+        }
+    }
+}
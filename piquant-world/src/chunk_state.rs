@@ -1,48 +1,42 @@
-use std::time::Instant;
+use crate::light::ChunkLight;
 
 pub trait ChunkState {
-    fn new(current_time: Instant, persistant: bool) -> Self
+    fn new() -> Self
     where
         Self: Sized + Sync + Send;
-    fn persistant(&self) -> bool;
-    fn touch(&mut self, current_time: Instant);
-    fn last_touched(&self) -> Instant;
+    /// Returns `true` if the chunk changed since it was last saved.
+    fn is_dirty(&self) -> bool;
+    fn set_dirty(&mut self, dirty: bool);
+    fn light(&self) -> &ChunkLight;
+    fn light_mut(&mut self) -> &mut ChunkLight;
 }
 
 // send and sync are required for the chunk state to be used in a par_iter_mut.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct DefaultChunkState {
-    last_touched: Instant,
-    persistant: bool,
+    dirty: bool,
+    light: ChunkLight,
 }
 
-impl Default for DefaultChunkState {
-    fn default() -> Self {
-        Self {
-            last_touched: Instant::now(),
-            persistant: false,
-        }
+impl ChunkState for DefaultChunkState {
+    fn new() -> Self {
+        Self::default()
     }
-}
 
-impl ChunkState for DefaultChunkState {
-    fn new(current_time: Instant, persistant: bool) -> Self {
-        Self {
-            last_touched: current_time,
-            persistant,
-        }
+    fn is_dirty(&self) -> bool {
+        self.dirty
     }
 
-    fn persistant(&self) -> bool {
-        self.persistant
+    fn set_dirty(&mut self, dirty: bool) {
+        self.dirty = dirty;
     }
 
-    fn touch(&mut self, current_time: Instant) {
-        self.last_touched = current_time;
+    fn light(&self) -> &ChunkLight {
+        &self.light
     }
 
-    fn last_touched(&self) -> Instant {
-        self.last_touched
+    fn light_mut(&mut self) -> &mut ChunkLight {
+        &mut self.light
     }
 }
 
@@ -0,0 +1,139 @@
+use std::collections::{BTreeSet, HashMap};
+
+use valence::{
+    prelude::{EntityId, GameMode},
+    uuid::Uuid,
+    vek::Aabb,
+};
+use valence_spatial_index::{
+    bvh::{Bvh, Node},
+    WithAabb,
+};
+
+/// An entity of a world as seen by selectors.
+#[derive(Clone, Debug)]
+pub struct EntityTarget {
+    pub id: EntityId,
+    pub uuid: Uuid,
+    /// The entity type without namespace, e.g. `cow`.
+    pub kind: String,
+    pub position: [f64; 3],
+    /// The username for players.
+    pub name: Option<String>,
+    /// The game mode for players.
+    pub game_mode: Option<GameMode>,
+    pub tags: BTreeSet<String>,
+}
+
+/// The entities of a world in a bounding volume hierarchy, rebuilt every tick
+/// so selectors don't have to check every entity of the server.
+#[derive(Default)]
+pub struct EntityIndex {
+    bvh: Bvh<WithAabb<EntityTarget>>,
+    /// Scoreboard tags, kept across rebuilds.
+    tags: HashMap<Uuid, BTreeSet<String>>,
+}
+
+impl EntityIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the indexed entities. The tags of each entity are filled in
+    /// from the ones added with [`EntityIndex::add_tag`].
+    pub fn rebuild(&mut self, entities: impl IntoIterator<Item = (EntityTarget, Aabb<f64>)>) {
+        let tags = &self.tags;
+
+        self.bvh
+            .rebuild(entities.into_iter().map(|(mut target, aabb)| {
+                if let Some(tags) = tags.get(&target.uuid) {
+                    target.tags = tags.clone();
+                }
+
+                WithAabb::new(target, aabb)
+            }));
+    }
+
+    pub fn get(&self, uuid: Uuid) -> Option<&EntityTarget> {
+        self.bvh
+            .iter()
+            .map(|entity| &entity.object)
+            .find(|entity| entity.uuid == uuid)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &EntityTarget> + '_ {
+        self.bvh.iter().map(|entity| &entity.object)
+    }
+
+    pub fn tags(&self, uuid: Uuid) -> impl Iterator<Item = &str> + '_ {
+        self.tags
+            .get(&uuid)
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+    }
+
+    /// Returns `false` if the entity already had the tag.
+    pub fn add_tag(&mut self, uuid: Uuid, tag: &str) -> bool {
+        let added = self.tags.entry(uuid).or_default().insert(tag.to_string());
+        self.sync_tags(uuid);
+        added
+    }
+
+    /// Returns `false` if the entity didn't have the tag.
+    pub fn remove_tag(&mut self, uuid: Uuid, tag: &str) -> bool {
+        let removed = self
+            .tags
+            .get_mut(&uuid)
+            .is_some_and(|tags| tags.remove(tag));
+        self.tags.retain(|_, tags| !tags.is_empty());
+        self.sync_tags(uuid);
+        removed
+    }
+
+    /// Copies changed tags into the indexed entity so they can be selected
+    /// before the next rebuild.
+    fn sync_tags(&mut self, uuid: Uuid) {
+        let tags = self.tags.get(&uuid).cloned().unwrap_or_default();
+
+        for entity in self.bvh.iter_mut() {
+            if entity.object.uuid == uuid {
+                entity.object.tags = tags.clone();
+            }
+        }
+    }
+
+    /// The entities whose hitbox collides with `bounds`.
+    pub fn query(&self, bounds: Aabb<f64>) -> Vec<&EntityTarget> {
+        let mut entities = Vec::new();
+
+        if let Some(root) = self.bvh.traverse() {
+            collect(root, bounds, &mut |entity| entities.push(entity));
+        }
+
+        entities
+    }
+}
+
+/// Visits the entities whose hitbox collides with `bounds`.
+fn collect<'a>(
+    node: Node<'a, WithAabb<EntityTarget>>,
+    bounds: Aabb<f64>,
+    f: &mut impl FnMut(&'a EntityTarget),
+) {
+    match node {
+        Node::Internal(internal) => {
+            let (aabb, left, right) = internal.split();
+
+            if aabb.collides_with_aabb(bounds) {
+                collect(left, bounds, f);
+                collect(right, bounds, f);
+            }
+        }
+        Node::Leaf(entity) => {
+            if entity.aabb.collides_with_aabb(bounds) {
+                f(&entity.object);
+            }
+        }
+    }
+}
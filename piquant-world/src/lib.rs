@@ -1,21 +1,55 @@
+mod anvil;
+mod border;
 mod chunk_state;
+mod entity_index;
+mod light;
+mod packets;
 mod seed;
+mod tickets;
+mod time;
+mod weather;
 mod world_state;
 
 pub use self::seed::Seed;
 pub use self::seed::SeedType;
 
+use std::time::{Duration, Instant};
+
 use noise::{NoiseFn, SuperSimplex};
 use rayon::prelude::ParallelIterator;
-use valence::{prelude::World as MCWorld, prelude::*, protocol::BlockState};
+use valence::{prelude::World as MCWorld, prelude::*, protocol::BlockState, uuid::Uuid};
+use valence_nbt::Compound;
 use vek::Lerp;
 
+pub use border::{BorderChanges, WorldBorder, MAX_BORDER_CENTER, MAX_BORDER_DIAMETER};
 pub use chunk_state::ChunkState;
-pub use world_state::WorldState;
+pub use entity_index::{EntityIndex, EntityTarget};
+pub use light::{
+    send_light, set_block_state, set_block_states, ChunkLight, LightKind, LightView,
+    RELIGHT_THRESHOLD,
+};
+pub use packets::{
+    SetBorderCenter, SetBorderLerpSize, SetBorderSize, SetBorderWarningDelay,
+    SetBorderWarningDistance, UpdateLight,
+};
+pub use tickets::{ChunkTickets, Ticket, TicketId, TicketKind};
+pub use time::{WorldTime, TICKS_PER_DAY};
+pub use weather::{Weather, WeatherChanges, WeatherKind};
+pub use world_state::{WorldState, WriteChunkError};
 
 pub use chunk_state::DefaultChunkState;
 pub use world_state::PiquantWorld;
 
+/// Valence sends clients the chunks two further than their view distance.
+pub const VIEW_PADDING: u8 = 2;
+
+/// How often level.dat is saved, in ticks.
+const LEVEL_SAVE_INTERVAL: i64 = 6000;
+
+/// How long a chunk that failed to save stays loaded before saving it is
+/// tried again.
+const SAVE_RETRY_DELAY: Duration = Duration::from_secs(10);
+
 pub struct World<G>
 where
     G: Config,
@@ -55,26 +89,165 @@ where
         self.seed.clone()
     }
 
-    pub fn queue(
+    /// Keeps the chunks a player can see loaded. The player's ticket only
+    /// changes when they cross into another chunk or change their view
+    /// distance.
+    pub fn update_view(
         &self,
         world: &mut MCWorld<G>,
+        player: Uuid,
         position: Vec3<f64>,
         distance: u8,
-        persistant: bool,
     ) {
-        for pos in ChunkPos::at(position.x, position.z).in_view(distance) {
-            let now = std::time::Instant::now();
+        let center = ChunkPos::at(position.x, position.z);
+        let level = distance + VIEW_PADDING;
+
+        let tickets = world.state.tickets_mut();
+        let kind = TicketKind::Player(player);
+
+        match tickets.find(&kind).map(|(id, _)| id) {
+            Some(id) => {
+                tickets.move_ticket(id, center, level);
+                tickets.set_expiry(id, None);
+            }
+            None => {
+                tickets.add(kind, center, level, None);
+            }
+        }
+    }
+
+    /// Lets the chunks around a player that left unload once the unload delay
+    /// has passed, unless the player comes back before that.
+    pub fn release_view(&self, world: &mut MCWorld<G>, player: Uuid) {
+        let tickets = world.state.tickets_mut();
+
+        if let Some((id, _)) = tickets.find(&TicketKind::Player(player)) {
+            let expires = Instant::now() + Duration::from_secs(self.chunk_unload_delay);
+            tickets.set_expiry(id, Some(expires));
+        }
+    }
+
+    /// Creates the chunks that tickets started holding and unloads the chunks
+    /// that no ticket holds anymore, saving them first if they changed. Chunks
+    /// outside the world border are never created.
+    fn queue(&self, world: &mut MCWorld<G>) {
+        let tickets = world.state.tickets_mut();
+        tickets.expire(Instant::now());
+
+        let (mut acquired, released) = tickets.take_changes();
+
+        // Held chunks that were skipped before might be inside the border now.
+        if world.state.border_mut().take_bounds_changed() {
+            let chunks = &world.chunks;
+            acquired.extend(
+                world
+                    .state
+                    .tickets()
+                    .held()
+                    .filter(|pos| chunks.get(*pos).is_none()),
+            );
+        }
+
+        for pos in released {
+            if world.chunks.get(pos).is_none() {
+                continue;
+            }
+
+            // Chunks that fail to save stay loaded and dirty, so saving them
+            // is tried again when the ticket holding them expires.
+            if let Err(e) = self.save_chunk(world, pos) {
+                tracing::error!("Failed to save chunk at ({}, {}): {e}", pos.x, pos.z);
+
+                let expires = Instant::now() + SAVE_RETRY_DELAY;
+                world
+                    .state
+                    .tickets_mut()
+                    .add(TicketKind::Unsaved, pos, 0, Some(expires));
+                continue;
+            }
 
             if let Some(chunk) = world.chunks.get_mut(pos) {
-                chunk.state.touch(now);
-            } else {
-                world.chunks.insert(
-                    pos,
-                    UnloadedChunk::default(),
-                    G::ChunkState::new(now, persistant),
-                );
+                chunk.set_deleted(true);
+            }
+        }
+
+        for pos in acquired {
+            if !world.state.border().contains_chunk(pos) {
+                continue;
+            }
+
+            match world.chunks.get_mut(pos) {
+                // The chunk was released and acquired again before valence
+                // removed it.
+                Some(chunk) => chunk.set_deleted(false),
+                None => {
+                    world
+                        .chunks
+                        .insert(pos, UnloadedChunk::default(), G::ChunkState::new());
+                }
+            }
+        }
+    }
+
+    /// Writes a loaded chunk to its region file if it changed since it was
+    /// last saved. Returns `true` if the chunk was written.
+    pub fn save_chunk(
+        &self,
+        world: &mut MCWorld<G>,
+        pos: ChunkPos,
+    ) -> Result<bool, WriteChunkError> {
+        if !world
+            .chunks
+            .get(pos)
+            .is_some_and(|chunk| chunk.state.is_dirty())
+        {
+            return Ok(false);
+        }
+
+        // A chunk that can't be read was generated again when it loaded, so
+        // there is nothing in it to keep.
+        let original = match world.state.read_chunk(pos.x, pos.z) {
+            Ok(Some(anvil_chunk)) => anvil_chunk.data,
+            Ok(None) | Err(_) => Compound::new(),
+        };
+
+        let Some(chunk) = world.chunks.get_mut(pos) else {
+            return Ok(false);
+        };
+
+        let data = anvil::to_anvil(&*chunk, chunk.state.light(), pos, 4, original);
+        world.state.write_chunk(pos.x, pos.z, &data)?;
+        chunk.state.set_dirty(false);
+
+        Ok(true)
+    }
+
+    /// Writes every chunk that changed since it was last saved, and
+    /// level.dat, right away. Returns the number of chunks written, chunks
+    /// that fail to save don't keep the others from being written.
+    pub fn save_all(&self, world: &mut MCWorld<G>) -> usize {
+        let dirty: Vec<_> = world
+            .chunks
+            .iter()
+            .filter(|(_, chunk)| chunk.state.is_dirty())
+            .map(|(pos, _)| pos)
+            .collect();
+
+        let mut saved = 0;
+
+        for pos in dirty {
+            match self.save_chunk(world, pos) {
+                Ok(true) => saved += 1,
+                Ok(false) => {}
+                Err(e) => tracing::error!("Failed to save chunk at ({}, {}): {e}", pos.x, pos.z),
             }
         }
+
+        if let Err(e) = world.state.write_level() {
+            tracing::error!("Failed to save level.dat: {e}");
+        }
+
+        saved
     }
 
     pub fn get_terrain_height(&self, world: &MCWorld<G>, position: Vec3<f64>) -> Option<i32> {
@@ -98,15 +271,18 @@ where
     }
 
     pub fn update(&self, world: &mut MCWorld<G>) {
-        // Remove chunks outside the view distance of players.
-        for (_, chunk) in world.chunks.iter_mut() {
-            if !chunk.state.persistant() {
-                if chunk.last_touched().elapsed().as_secs() > self.chunk_unload_delay {
-                    chunk.set_deleted(true);
-                }
+        world.state.time_mut().tick();
+        world.state.weather_mut().tick();
+        world.state.border_mut().tick();
+
+        if world.state.time().game_time() % LEVEL_SAVE_INTERVAL == 0 {
+            if let Err(e) = world.state.write_level() {
+                tracing::error!("Failed to save level.dat: {e}");
             }
         }
 
+        self.queue(world);
+
         // Generate chunk data for chunks created this tick.
         world.chunks.par_iter_mut().for_each(|(pos, chunk)| {
             if !chunk.created_this_tick() {
@@ -116,23 +292,52 @@ where
             // try to read chunk from file,
             // if it doesn't exist, generate it
 
-            match world.state.read_chunk(pos.x, pos.z) {
+            let light = match world.state.read_chunk(pos.x, pos.z) {
                 Ok(Some(anvil_chunk)) => {
                     if let Err(e) = valence_anvil::to_valence(&anvil_chunk.data, chunk, 4, |_| {
                         BiomeId::default()
                     }) {
-                        eprintln!("Failed to convert chunk at ({}, {}): {e}", pos.x, pos.z);
+                        tracing::error!("Failed to convert chunk at ({}, {}): {e}", pos.x, pos.z);
                     }
+
+                    // Reuse the light saved with the chunk when there is any.
+                    ChunkLight::from_anvil(&anvil_chunk.data, chunk.section_count(), 4)
                 }
                 Ok(None) => {
                     // No chunk at this position.
                     self.generate_chunk(pos, chunk);
+                    chunk.state.set_dirty(true);
+                    None
                 }
                 Err(_) => {
                     self.generate_chunk(pos, chunk);
+                    chunk.state.set_dirty(true);
+                    None
                 }
-            }
+            };
+
+            let light = light.unwrap_or_else(|| light::compute(&*chunk));
+            *chunk.state.light_mut() = light;
+            chunk.state.light_mut().mark_dirty();
         });
+
+        // Let light flow between the new chunks and their neighbours.
+        let created: Vec<_> = world
+            .chunks
+            .iter()
+            .filter(|(_, chunk)| chunk.created_this_tick())
+            .map(|(pos, _)| pos)
+            .collect();
+
+        for pos in created {
+            light::stitch(&mut world.chunks, pos);
+        }
+
+        // Light changed this tick is sent to clients next tick, after valence
+        // has sent them the chunks themselves.
+        for (_, chunk) in world.chunks.iter_mut() {
+            chunk.state.light_mut().promote();
+        }
     }
 
     fn generate_chunk(&self, pos: ChunkPos, chunk: &mut LoadedChunk<G>) {
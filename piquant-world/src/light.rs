@@ -0,0 +1,777 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use valence::{
+    prelude::{Chunk, ChunkPos, Chunks, Client, Config, World as MCWorld, WorldId},
+    protocol::{BlockPos, BlockState, LengthPrefixedArray, VarInt},
+};
+use valence_nbt::{Compound, List, Value};
+
+use crate::{packets::UpdateLight, ChunkState};
+
+const SECTION_LEN: usize = 2048;
+
+pub const MAX_LIGHT: u8 = 15;
+
+/// How many blocks of a chunk [`set_block_states`] changes one by one before
+/// it computes the light of the whole chunk instead.
+pub const RELIGHT_THRESHOLD: usize = 64;
+
+/// Offsets to the six neighbours of a block. Down comes first so sky light
+/// reaches the bottom of open columns before it spreads sideways.
+const DIRECTIONS: [(i32, i32, i32); 6] = [
+    (0, -1, 0),
+    (0, 1, 0),
+    (-1, 0, 0),
+    (1, 0, 0),
+    (0, 0, -1),
+    (0, 0, 1),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LightKind {
+    Sky,
+    Block,
+}
+
+impl LightKind {
+    const ALL: [LightKind; 2] = [LightKind::Sky, LightKind::Block];
+
+    /// The level a neighbour receives from a block lit at `level` when light
+    /// travels in direction `dir`. Full sky light travels straight down
+    /// without fading.
+    fn spread(self, level: u8, dir: (i32, i32, i32)) -> u8 {
+        if self == LightKind::Sky && dir == DIRECTIONS[0] && level == MAX_LIGHT {
+            MAX_LIGHT
+        } else {
+            level.saturating_sub(1)
+        }
+    }
+}
+
+/// Sky and block light levels of a single chunk, stored as one nibble per
+/// block in the same layout as the protocol and Anvil files.
+#[derive(Clone, Debug, Default)]
+pub struct ChunkLight {
+    sky: Vec<LengthPrefixedArray<u8, SECTION_LEN>>,
+    block: Vec<LengthPrefixedArray<u8, SECTION_LEN>>,
+    /// The light changed during the current tick.
+    dirty: bool,
+    /// The light changed during the previous tick and has to be sent to
+    /// clients that can see the chunk.
+    pending: bool,
+}
+
+impl ChunkLight {
+    pub fn new(section_count: usize) -> Self {
+        Self {
+            sky: vec![LengthPrefixedArray([0; SECTION_LEN]); section_count],
+            block: vec![LengthPrefixedArray([0; SECTION_LEN]); section_count],
+            dirty: false,
+            pending: false,
+        }
+    }
+
+    /// Reads the light stored in an Anvil chunk. `None` is returned when the
+    /// chunk was saved without (complete) light data, in which case the light
+    /// has to be computed instead.
+    pub fn from_anvil(nbt: &Compound, section_count: usize, sect_offset: i32) -> Option<Self> {
+        match nbt.get("isLightOn") {
+            Some(Value::Byte(1)) => {}
+            _ => return None,
+        }
+
+        let Some(Value::List(List::Compound(sections))) = nbt.get("sections") else {
+            return None;
+        };
+
+        let mut light = Self::new(section_count);
+        let mut has_sky = vec![false; section_count];
+
+        for section in sections {
+            let Some(Value::Byte(sect_y)) = section.get("Y") else {
+                return None;
+            };
+
+            let sect_y = *sect_y as i32 + sect_offset;
+
+            if sect_y < 0 || sect_y as usize >= section_count {
+                continue;
+            }
+
+            let sect_y = sect_y as usize;
+
+            if let Some(Value::ByteArray(data)) = section.get("SkyLight") {
+                copy_nibbles(&mut light.sky[sect_y], data)?;
+                has_sky[sect_y] = true;
+            }
+
+            if let Some(Value::ByteArray(data)) = section.get("BlockLight") {
+                copy_nibbles(&mut light.block[sect_y], data)?;
+            }
+        }
+
+        // Vanilla leaves out the sky light of sections above the terrain,
+        // those are fully lit. A missing section below that is unexpected.
+        let top = has_sky.iter().rposition(|has| *has);
+
+        for (sect_y, has) in has_sky.iter().enumerate() {
+            match top {
+                Some(top) if sect_y > top => light.sky[sect_y].0.fill(0xff),
+                None => light.sky[sect_y].0.fill(0xff),
+                _ if !has => return None,
+                _ => {}
+            }
+        }
+
+        Some(light)
+    }
+
+    pub fn section_count(&self) -> usize {
+        self.sky.len()
+    }
+
+    /// Returns `true` if the light has not been computed yet.
+    pub fn is_empty(&self) -> bool {
+        self.sky.is_empty()
+    }
+
+    /// Gets the light level at the provided offsets in the chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the offsets are outside the bounds of the chunk.
+    pub fn get(&self, kind: LightKind, x: usize, y: usize, z: usize) -> u8 {
+        let idx = x + z * 16 + y % 16 * 256;
+        let byte = self.sections(kind)[y / 16].0[idx / 2];
+
+        (byte >> (idx % 2 * 4)) & 0xf
+    }
+
+    /// Sets the light level at the provided offsets in the chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the offsets are outside the bounds of the chunk.
+    pub fn set(&mut self, kind: LightKind, x: usize, y: usize, z: usize, level: u8) {
+        let idx = x + z * 16 + y % 16 * 256;
+        let shift = idx % 2 * 4;
+        let sections = match kind {
+            LightKind::Sky => &mut self.sky,
+            LightKind::Block => &mut self.block,
+        };
+        let byte = &mut sections[y / 16].0[idx / 2];
+
+        *byte = (*byte & !(0xf << shift)) | ((level & 0xf) << shift);
+        self.dirty = true;
+    }
+
+    /// Returns `true` if the light changed during the previous tick.
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    pub(crate) fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Moves the changes made during this tick into the set of changes that
+    /// will be sent to clients during the next tick.
+    pub(crate) fn promote(&mut self) {
+        self.pending = self.dirty;
+        self.dirty = false;
+    }
+
+    /// Gets the raw nibbles of a single section.
+    pub fn section(&self, kind: LightKind, sect_y: usize) -> &[u8] {
+        &self.sections(kind)[sect_y].0
+    }
+
+    fn sections(&self, kind: LightKind) -> &[LengthPrefixedArray<u8, SECTION_LEN>] {
+        match kind {
+            LightKind::Sky => &self.sky,
+            LightKind::Block => &self.block,
+        }
+    }
+
+    /// Creates the light update packet for this chunk. The protocol has an
+    /// extra section below and above the world, which are dark and fully
+    /// lit respectively.
+    pub fn packet(&self, pos: ChunkPos) -> UpdateLight {
+        let mut packet = UpdateLight {
+            chunk_x: VarInt(pos.x),
+            chunk_z: VarInt(pos.z),
+            trust_edges: true,
+            sky_light_mask: Vec::new(),
+            block_light_mask: Vec::new(),
+            empty_sky_light_mask: Vec::new(),
+            empty_block_light_mask: Vec::new(),
+            sky_light_arrays: Vec::new(),
+            block_light_arrays: Vec::new(),
+        };
+
+        let count = self.section_count() + 2;
+        let words = count.div_ceil(64);
+
+        for mask in [
+            &mut packet.sky_light_mask,
+            &mut packet.block_light_mask,
+            &mut packet.empty_sky_light_mask,
+            &mut packet.empty_block_light_mask,
+        ] {
+            mask.resize(words, 0);
+        }
+
+        let full = LengthPrefixedArray([0xff; SECTION_LEN]);
+
+        for i in 0..count {
+            let (sky, block) = if i == 0 {
+                (None, None)
+            } else if i == count - 1 {
+                (Some(&full), None)
+            } else {
+                (Some(&self.sky[i - 1]), Some(&self.block[i - 1]))
+            };
+
+            let bit = 1 << (i % 64);
+
+            match sky.filter(|s| s.0.iter().any(|b| *b != 0)) {
+                Some(sky) => {
+                    packet.sky_light_mask[i / 64] |= bit;
+                    packet.sky_light_arrays.push(*sky);
+                }
+                None => packet.empty_sky_light_mask[i / 64] |= bit,
+            }
+
+            match block.filter(|b| b.0.iter().any(|b| *b != 0)) {
+                Some(block) => {
+                    packet.block_light_mask[i / 64] |= bit;
+                    packet.block_light_arrays.push(*block);
+                }
+                None => packet.empty_block_light_mask[i / 64] |= bit,
+            }
+        }
+
+        packet
+    }
+}
+
+fn copy_nibbles(dest: &mut LengthPrefixedArray<u8, SECTION_LEN>, src: &[i8]) -> Option<()> {
+    if src.len() != SECTION_LEN {
+        return None;
+    }
+
+    for (d, s) in dest.0.iter_mut().zip(src) {
+        *d = *s as u8;
+    }
+
+    Some(())
+}
+
+/// The chunks a client could see the last time light was sent to it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LightView {
+    world: Option<WorldId>,
+    center: ChunkPos,
+    distance: u8,
+}
+
+impl LightView {
+    fn contains(&self, world: WorldId, pos: ChunkPos) -> bool {
+        self.world == Some(world) && self.center.is_in_view(pos, self.distance)
+    }
+}
+
+/// Computes the light of a single chunk without looking at its neighbours.
+/// Light crossing chunk borders is handled by [`stitch`] once the
+/// neighbours are loaded.
+pub fn compute<C: Chunk>(chunk: &C) -> ChunkLight {
+    let height = chunk.section_count() * 16;
+    let mut light = ChunkLight::new(chunk.section_count());
+    let mut queue = VecDeque::new();
+
+    // The first block above the highest opaque block of every column,
+    // indexed by `x + z * 16`.
+    let heightmap: Vec<usize> = (0..256)
+        .map(|i| {
+            (0..height)
+                .rev()
+                .find(|&y| chunk.block_state(i % 16, y, i / 16).is_opaque())
+                .map_or(0, |y| y + 1)
+        })
+        .collect();
+
+    for (i, &top) in heightmap.iter().enumerate() {
+        for y in top..height {
+            light.set(LightKind::Sky, i % 16, y, i / 16, MAX_LIGHT);
+        }
+    }
+
+    // Sky light only has to spread sideways where a neighbouring column is
+    // taller.
+    for (i, &top) in heightmap.iter().enumerate() {
+        let (x, z) = (i % 16, i / 16);
+        let mut neighbour_top = top;
+
+        for (dx, _, dz) in &DIRECTIONS[2..] {
+            let nx = x as i32 + dx;
+            let nz = z as i32 + dz;
+
+            if (0..16).contains(&nx) && (0..16).contains(&nz) {
+                neighbour_top = neighbour_top.max(heightmap[(nx + nz * 16) as usize]);
+            }
+        }
+
+        for y in top..neighbour_top {
+            queue.push_back((x, y, z));
+        }
+    }
+
+    propagate_local(chunk, &mut light, LightKind::Sky, &mut queue);
+
+    for y in 0..height {
+        for z in 0..16 {
+            for x in 0..16 {
+                let luminance = chunk.block_state(x, y, z).luminance();
+
+                if luminance > 0 {
+                    light.set(LightKind::Block, x, y, z, luminance);
+                    queue.push_back((x, y, z));
+                }
+            }
+        }
+    }
+
+    propagate_local(chunk, &mut light, LightKind::Block, &mut queue);
+
+    light
+}
+
+fn propagate_local<C: Chunk>(
+    chunk: &C,
+    light: &mut ChunkLight,
+    kind: LightKind,
+    queue: &mut VecDeque<(usize, usize, usize)>,
+) {
+    let height = chunk.section_count() as i32 * 16;
+
+    while let Some((x, y, z)) = queue.pop_front() {
+        let level = light.get(kind, x, y, z);
+
+        for dir in DIRECTIONS {
+            let (nx, ny, nz) = (x as i32 + dir.0, y as i32 + dir.1, z as i32 + dir.2);
+
+            if !(0..16).contains(&nx) || !(0..height).contains(&ny) || !(0..16).contains(&nz) {
+                continue;
+            }
+
+            let (nx, ny, nz) = (nx as usize, ny as usize, nz as usize);
+
+            if chunk.block_state(nx, ny, nz).is_opaque() {
+                continue;
+            }
+
+            let new = kind.spread(level, dir);
+
+            if new > light.get(kind, nx, ny, nz) {
+                light.set(kind, nx, ny, nz, new);
+                queue.push_back((nx, ny, nz));
+            }
+        }
+    }
+}
+
+/// Spreads light across the borders between the chunk at `pos` and its
+/// loaded neighbours. Only blocks brighter than the block across the border
+/// can light it, so only those are queued.
+pub fn stitch<G>(chunks: &mut Chunks<G>, pos: ChunkPos)
+where
+    G: Config,
+    G::ChunkState: ChunkState,
+{
+    let height = chunks.height() as i32;
+    let (min_x, min_z) = (pos.x * 16, pos.z * 16);
+
+    for kind in LightKind::ALL {
+        let mut queue = VecDeque::new();
+
+        for y in 0..height {
+            for i in 0..16 {
+                for (inside, outside) in [
+                    ((min_x + i, min_z), (min_x + i, min_z - 1)),
+                    ((min_x + i, min_z + 15), (min_x + i, min_z + 16)),
+                    ((min_x, min_z + i), (min_x - 1, min_z + i)),
+                    ((min_x + 15, min_z + i), (min_x + 16, min_z + i)),
+                ] {
+                    let inside = (inside.0, y, inside.1);
+                    let outside = (outside.0, y, outside.1);
+
+                    let (Some(a), Some(b)) = (
+                        light_at(chunks, kind, inside),
+                        light_at(chunks, kind, outside),
+                    ) else {
+                        continue;
+                    };
+
+                    if a > b + 1 {
+                        queue.push_back(inside);
+                    } else if b > a + 1 {
+                        queue.push_back(outside);
+                    }
+                }
+            }
+        }
+
+        propagate(chunks, kind, &mut queue);
+    }
+}
+
+/// Sets the block at an absolute block position and updates the light around
+/// it. The previous block state is returned, or `None` if the position is not
+/// inside a loaded chunk.
+pub fn set_block_state<G>(
+    world: &mut MCWorld<G>,
+    pos: impl Into<BlockPos>,
+    block: BlockState,
+) -> Option<BlockState>
+where
+    G: Config,
+    G::ChunkState: ChunkState,
+{
+    let pos = pos.into();
+    let chunks = &mut world.chunks;
+
+    let y = pos.y - chunks.min_y();
+
+    if y < 0 || y >= chunks.height() as i32 {
+        return None;
+    }
+
+    let chunk = chunks.get_mut(ChunkPos::from(pos))?;
+
+    let previous = chunk.set_block_state(
+        pos.x.rem_euclid(16) as usize,
+        y as usize,
+        pos.z.rem_euclid(16) as usize,
+        block,
+    );
+
+    if previous == block {
+        return Some(previous);
+    }
+
+    chunk.state.set_dirty(true);
+
+    if !chunk.state.light().is_empty() {
+        update(chunks, (pos.x, y, pos.z), block);
+    }
+
+    Some(previous)
+}
+
+/// Sets many blocks at once and returns the previous state of every block
+/// that changed. Blocks outside of loaded chunks or the height of the world
+/// are skipped.
+///
+/// The blocks are set chunk by chunk. A chunk where only a few blocks change
+/// has the light around each of them updated, while a chunk with more
+/// changes than [`RELIGHT_THRESHOLD`] gets its light and the light of its
+/// neighbours computed once at the end, which is much cheaper than updating
+/// thousands of blocks one by one.
+pub fn set_block_states<G>(
+    world: &mut MCWorld<G>,
+    blocks: impl IntoIterator<Item = (BlockPos, BlockState)>,
+) -> Vec<(BlockPos, BlockState)>
+where
+    G: Config,
+    G::ChunkState: ChunkState,
+{
+    let mut batches: BTreeMap<ChunkPos, Vec<(BlockPos, BlockState)>> = BTreeMap::new();
+
+    for (pos, block) in blocks {
+        batches
+            .entry(ChunkPos::from(pos))
+            .or_default()
+            .push((pos, block));
+    }
+
+    let mut previous = Vec::new();
+    let mut relight = BTreeSet::new();
+
+    for (chunk_pos, batch) in batches {
+        if batch.len() <= RELIGHT_THRESHOLD {
+            for (pos, block) in batch {
+                match set_block_state(world, pos, block) {
+                    Some(before) if before != block => previous.push((pos, before)),
+                    _ => {}
+                }
+            }
+
+            continue;
+        }
+
+        let (min_y, height) = (world.chunks.min_y(), world.chunks.height() as i32);
+
+        let Some(chunk) = world.chunks.get_mut(chunk_pos) else {
+            continue;
+        };
+
+        let changed = previous.len();
+
+        for (pos, block) in batch {
+            let y = pos.y - min_y;
+
+            if y < 0 || y >= height {
+                continue;
+            }
+
+            let before = chunk.set_block_state(
+                pos.x.rem_euclid(16) as usize,
+                y as usize,
+                pos.z.rem_euclid(16) as usize,
+                block,
+            );
+
+            if before != block {
+                previous.push((pos, before));
+            }
+        }
+
+        if previous.len() > changed {
+            chunk.state.set_dirty(true);
+
+            if !chunk.state.light().is_empty() {
+                relight.insert(chunk_pos);
+            }
+        }
+    }
+
+    // Light from the changed chunks may have reached into their neighbours,
+    // so those are computed again as well.
+    let around: BTreeSet<ChunkPos> = relight
+        .iter()
+        .flat_map(|pos| {
+            (-1..=1)
+                .flat_map(move |dz| (-1..=1).map(move |dx| ChunkPos::new(pos.x + dx, pos.z + dz)))
+        })
+        .filter(|pos| {
+            world
+                .chunks
+                .get(*pos)
+                .is_some_and(|chunk| !chunk.state.light().is_empty())
+        })
+        .collect();
+
+    for pos in &around {
+        if let Some(chunk) = world.chunks.get_mut(*pos) {
+            let light = compute(&*chunk);
+            *chunk.state.light_mut() = light;
+            chunk.state.light_mut().mark_dirty();
+        }
+    }
+
+    for pos in around {
+        stitch(&mut world.chunks, pos);
+    }
+
+    previous
+}
+
+/// Relights the world around a block that was just changed. `p` uses chunk
+/// space for the Y coordinate.
+fn update<G>(chunks: &mut Chunks<G>, p: (i32, i32, i32), block: BlockState)
+where
+    G: Config,
+    G::ChunkState: ChunkState,
+{
+    let height = chunks.height() as i32;
+
+    for kind in LightKind::ALL {
+        let mut queue = VecDeque::new();
+
+        let level = light_at(chunks, kind, p).unwrap_or(0);
+
+        if level > 0 {
+            set_light_at(chunks, kind, p, 0);
+            remove(chunks, kind, p, level, &mut queue);
+        }
+
+        let seed = match kind {
+            LightKind::Sky if p.1 == height - 1 && !block.is_opaque() => MAX_LIGHT,
+            LightKind::Sky => 0,
+            LightKind::Block => block.luminance(),
+        };
+
+        if seed > 0 {
+            set_light_at(chunks, kind, p, seed);
+            queue.push_back(p);
+        }
+
+        for dir in DIRECTIONS {
+            queue.push_back((p.0 + dir.0, p.1 + dir.1, p.2 + dir.2));
+        }
+
+        propagate(chunks, kind, &mut queue);
+    }
+}
+
+/// Darkens every block that was lit by the block at `p`. Blocks at the edge
+/// of the darkened area are queued so their light can flow back in.
+fn remove<G>(
+    chunks: &mut Chunks<G>,
+    kind: LightKind,
+    p: (i32, i32, i32),
+    level: u8,
+    increase: &mut VecDeque<(i32, i32, i32)>,
+) where
+    G: Config,
+    G::ChunkState: ChunkState,
+{
+    let mut queue = VecDeque::from([(p, level)]);
+
+    while let Some((p, level)) = queue.pop_front() {
+        for dir in DIRECTIONS {
+            let n = (p.0 + dir.0, p.1 + dir.1, p.2 + dir.2);
+
+            let Some(neighbour) = light_at(chunks, kind, n) else {
+                continue;
+            };
+
+            if neighbour == 0 {
+                continue;
+            }
+
+            if neighbour < level || kind.spread(level, dir) == MAX_LIGHT && neighbour == MAX_LIGHT {
+                set_light_at(chunks, kind, n, 0);
+                queue.push_back((n, neighbour));
+            } else {
+                increase.push_back(n);
+            }
+        }
+    }
+}
+
+/// Spreads light outwards from every queued block, crossing chunk borders
+/// where the neighbouring chunk is loaded.
+fn propagate<G>(chunks: &mut Chunks<G>, kind: LightKind, queue: &mut VecDeque<(i32, i32, i32)>)
+where
+    G: Config,
+    G::ChunkState: ChunkState,
+{
+    while let Some(p) = queue.pop_front() {
+        let level = match light_at(chunks, kind, p) {
+            Some(level) if level > 0 => level,
+            _ => continue,
+        };
+
+        for dir in DIRECTIONS {
+            let n = (p.0 + dir.0, p.1 + dir.1, p.2 + dir.2);
+
+            let Some(current) = light_at(chunks, kind, n) else {
+                continue;
+            };
+
+            if block_at(chunks, n).is_none_or(|b| b.is_opaque()) {
+                continue;
+            }
+
+            let new = kind.spread(level, dir);
+
+            if new > current {
+                set_light_at(chunks, kind, n, new);
+                queue.push_back(n);
+            }
+        }
+    }
+}
+
+fn block_at<G: Config>(chunks: &Chunks<G>, p: (i32, i32, i32)) -> Option<BlockState> {
+    let chunk = chunks.get(ChunkPos::new(p.0.div_euclid(16), p.2.div_euclid(16)))?;
+
+    if p.1 < 0 || p.1 as usize >= chunk.section_count() * 16 {
+        return None;
+    }
+
+    Some(chunk.block_state(
+        p.0.rem_euclid(16) as usize,
+        p.1 as usize,
+        p.2.rem_euclid(16) as usize,
+    ))
+}
+
+fn light_at<G>(chunks: &Chunks<G>, kind: LightKind, p: (i32, i32, i32)) -> Option<u8>
+where
+    G: Config,
+    G::ChunkState: ChunkState,
+{
+    let chunk = chunks.get(ChunkPos::new(p.0.div_euclid(16), p.2.div_euclid(16)))?;
+    let light = chunk.state.light();
+
+    if p.1 < 0 || p.1 as usize >= light.section_count() * 16 {
+        return None;
+    }
+
+    Some(light.get(
+        kind,
+        p.0.rem_euclid(16) as usize,
+        p.1 as usize,
+        p.2.rem_euclid(16) as usize,
+    ))
+}
+
+fn set_light_at<G>(chunks: &mut Chunks<G>, kind: LightKind, p: (i32, i32, i32), level: u8)
+where
+    G: Config,
+    G::ChunkState: ChunkState,
+{
+    if let Some(chunk) = chunks.get_mut(ChunkPos::new(p.0.div_euclid(16), p.2.div_euclid(16))) {
+        chunk.state.light_mut().set(
+            kind,
+            p.0.rem_euclid(16) as usize,
+            p.1 as usize,
+            p.2.rem_euclid(16) as usize,
+            level,
+        );
+    }
+}
+
+/// Sends the light of every chunk that became visible to the client since
+/// the last call, as well as every visible chunk whose light changed during
+/// the previous tick.
+///
+/// Light has to arrive after the chunk data, which valence sends at the end
+/// of the tick. This should therefore be called before the client's events
+/// are handled, while its position still matches what valence used.
+pub fn send_light<G>(
+    world_id: WorldId,
+    world: &MCWorld<G>,
+    client: &mut Client<G>,
+    view: &mut LightView,
+) where
+    G: Config,
+    G::ChunkState: ChunkState,
+{
+    let center = ChunkPos::at(client.position().x, client.position().z);
+    let distance = client.view_distance();
+
+    for pos in center.in_view(distance) {
+        let Some(chunk) = world.chunks.get(pos) else {
+            continue;
+        };
+
+        let light = chunk.state.light();
+
+        if light.is_empty() {
+            continue;
+        }
+
+        if light.is_pending() || !view.contains(world_id, pos) {
+            client.queue_packet(&light.packet(pos));
+        }
+    }
+
+    *view = LightView {
+        world: Some(world_id),
+        center,
+        distance,
+    };
+}
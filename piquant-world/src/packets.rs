@@ -0,0 +1,53 @@
+use valence::protocol::{Encode, EncodePacket, LengthPrefixedArray, VarInt, VarLong};
+
+// Clientbound packets that are not (yet) provided by valence.
+
+/// Replaces the sky and block light of a chunk that is already loaded on the
+/// client.
+#[derive(Clone, Debug, Encode, EncodePacket)]
+#[packet_id = 0x23]
+pub struct UpdateLight {
+    pub chunk_x: VarInt,
+    pub chunk_z: VarInt,
+    pub trust_edges: bool,
+    pub sky_light_mask: Vec<u64>,
+    pub block_light_mask: Vec<u64>,
+    pub empty_sky_light_mask: Vec<u64>,
+    pub empty_block_light_mask: Vec<u64>,
+    pub sky_light_arrays: Vec<LengthPrefixedArray<u8, 2048>>,
+    pub block_light_arrays: Vec<LengthPrefixedArray<u8, 2048>>,
+}
+
+#[derive(Clone, Debug, Encode, EncodePacket)]
+#[packet_id = 0x43]
+pub struct SetBorderCenter {
+    pub x: f64,
+    pub z: f64,
+}
+
+/// Moves the border to a new diameter over `speed` milliseconds.
+#[derive(Clone, Debug, Encode, EncodePacket)]
+#[packet_id = 0x44]
+pub struct SetBorderLerpSize {
+    pub old_diameter: f64,
+    pub new_diameter: f64,
+    pub speed: VarLong,
+}
+
+#[derive(Clone, Debug, Encode, EncodePacket)]
+#[packet_id = 0x45]
+pub struct SetBorderSize {
+    pub diameter: f64,
+}
+
+#[derive(Clone, Debug, Encode, EncodePacket)]
+#[packet_id = 0x46]
+pub struct SetBorderWarningDelay {
+    pub warning_time: VarInt,
+}
+
+#[derive(Clone, Debug, Encode, EncodePacket)]
+#[packet_id = 0x47]
+pub struct SetBorderWarningDistance {
+    pub warning_blocks: VarInt,
+}
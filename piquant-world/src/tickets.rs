@@ -0,0 +1,307 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
+
+use valence::{prelude::ChunkPos, uuid::Uuid};
+
+/// Why a ticket is keeping chunks loaded.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum TicketKind {
+    /// The chunks a player can see.
+    Player(Uuid),
+    /// The area around the world spawn.
+    Spawn,
+    /// Chunks force loaded by an administrator.
+    Forced,
+    /// Chunks held by a plugin, identified by name.
+    Plugin(String),
+    /// A chunk that failed to save when it was unloaded, kept loaded until
+    /// saving it is tried again.
+    Unsaved,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TicketId(u64);
+
+#[derive(Clone, Debug)]
+pub struct Ticket {
+    pub kind: TicketKind,
+    pub center: ChunkPos,
+    /// The distance in chunks around `center` that this ticket holds. A level
+    /// of 0 holds only the center chunk.
+    pub level: u8,
+    /// When the ticket is removed automatically. `None` keeps the ticket until
+    /// it is removed by hand.
+    pub expires: Option<Instant>,
+}
+
+impl Ticket {
+    fn chunks(&self) -> impl Iterator<Item = ChunkPos> {
+        let center = self.center;
+        let level = self.level as i32;
+
+        (center.z - level..=center.z + level)
+            .flat_map(move |z| (center.x - level..=center.x + level).map(move |x| (x, z)))
+            .filter(move |(x, z)| {
+                let (dx, dz) = (x - center.x, z - center.z);
+                dx * dx + dz * dz <= level * level
+            })
+            .map(|(x, z)| ChunkPos::new(x, z))
+    }
+}
+
+/// Reference counted chunk tickets. A chunk stays loaded for as long as at
+/// least one ticket holds it.
+#[derive(Debug, Default)]
+pub struct ChunkTickets {
+    tickets: HashMap<TicketId, Ticket>,
+    /// The number of tickets holding each chunk.
+    holders: HashMap<ChunkPos, usize>,
+    /// Chunks that became held since the last call to `take_changes`.
+    acquired: HashSet<ChunkPos>,
+    /// Chunks that stopped being held since the last call to `take_changes`.
+    released: HashSet<ChunkPos>,
+    next_id: u64,
+}
+
+impl ChunkTickets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(
+        &mut self,
+        kind: TicketKind,
+        center: ChunkPos,
+        level: u8,
+        expires: Option<Instant>,
+    ) -> TicketId {
+        let id = TicketId(self.next_id);
+        self.next_id += 1;
+
+        let ticket = Ticket {
+            kind,
+            center,
+            level,
+            expires,
+        };
+
+        self.acquire(&ticket);
+        self.tickets.insert(id, ticket);
+
+        id
+    }
+
+    pub fn remove(&mut self, id: TicketId) -> Option<Ticket> {
+        let ticket = self.tickets.remove(&id)?;
+        self.release(&ticket);
+
+        Some(ticket)
+    }
+
+    pub fn get(&self, id: TicketId) -> Option<&Ticket> {
+        self.tickets.get(&id)
+    }
+
+    /// Returns the first ticket of the given kind.
+    pub fn find(&self, kind: &TicketKind) -> Option<(TicketId, &Ticket)> {
+        self.iter().find(|(_, ticket)| &ticket.kind == kind)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (TicketId, &Ticket)> {
+        self.tickets.iter().map(|(id, ticket)| (*id, ticket))
+    }
+
+    /// Moves a ticket to a new center and level. Chunks held by both the old
+    /// and the new area stay loaded.
+    pub fn move_ticket(&mut self, id: TicketId, center: ChunkPos, level: u8) {
+        let Some(mut ticket) = self.tickets.remove(&id) else {
+            return;
+        };
+
+        if ticket.center != center || ticket.level != level {
+            let old = ticket.clone();
+
+            ticket.center = center;
+            ticket.level = level;
+
+            self.acquire(&ticket);
+            self.release(&old);
+        }
+
+        self.tickets.insert(id, ticket);
+    }
+
+    pub fn set_expiry(&mut self, id: TicketId, expires: Option<Instant>) {
+        if let Some(ticket) = self.tickets.get_mut(&id) {
+            ticket.expires = expires;
+        }
+    }
+
+    /// Returns `true` if at least one ticket holds the chunk.
+    pub fn is_held(&self, pos: ChunkPos) -> bool {
+        self.holders.contains_key(&pos)
+    }
+
+    /// Returns every chunk held by at least one ticket.
+    pub fn held(&self) -> impl Iterator<Item = ChunkPos> + '_ {
+        self.holders.keys().copied()
+    }
+
+    /// Removes every ticket that expired before `now`.
+    pub fn expire(&mut self, now: Instant) {
+        let expired: Vec<_> = self
+            .tickets
+            .iter()
+            .filter(|(_, ticket)| ticket.expires.is_some_and(|expires| expires <= now))
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in expired {
+            self.remove(id);
+        }
+    }
+
+    /// Returns the chunks that became held and the chunks that were released
+    /// since the last call.
+    pub(crate) fn take_changes(&mut self) -> (Vec<ChunkPos>, Vec<ChunkPos>) {
+        let holders = &self.holders;
+
+        let acquired = self
+            .acquired
+            .drain()
+            .filter(|pos| holders.contains_key(pos))
+            .collect();
+        let released = self
+            .released
+            .drain()
+            .filter(|pos| !holders.contains_key(pos))
+            .collect();
+
+        (acquired, released)
+    }
+
+    fn acquire(&mut self, ticket: &Ticket) {
+        for pos in ticket.chunks() {
+            let count = self.holders.entry(pos).or_insert(0);
+            *count += 1;
+
+            if *count == 1 {
+                self.acquired.insert(pos);
+            }
+        }
+    }
+
+    fn release(&mut self, ticket: &Ticket) {
+        for pos in ticket.chunks() {
+            if let Some(count) = self.holders.get_mut(&pos) {
+                *count -= 1;
+
+                if *count == 0 {
+                    self.holders.remove(&pos);
+                    self.released.insert(pos);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn sorted(mut chunks: Vec<ChunkPos>) -> Vec<(i32, i32)> {
+        chunks.sort_by_key(|pos| (pos.x, pos.z));
+        chunks.into_iter().map(|pos| (pos.x, pos.z)).collect()
+    }
+
+    #[test]
+    fn level_holds_chunks_within_radius() {
+        let mut tickets = ChunkTickets::new();
+        tickets.add(TicketKind::Spawn, ChunkPos::new(0, 0), 1, None);
+
+        let (acquired, released) = tickets.take_changes();
+
+        assert_eq!(sorted(acquired), [(-1, 0), (0, -1), (0, 0), (0, 1), (1, 0)]);
+        assert!(released.is_empty());
+    }
+
+    #[test]
+    fn chunk_is_released_by_last_holder() {
+        let mut tickets = ChunkTickets::new();
+        let pos = ChunkPos::new(3, -2);
+
+        let first = tickets.add(TicketKind::Forced, pos, 0, None);
+        let second = tickets.add(TicketKind::Spawn, pos, 0, None);
+        assert_eq!(sorted(tickets.take_changes().0), [(3, -2)]);
+
+        tickets.remove(first);
+        assert!(tickets.is_held(pos));
+        assert_eq!(tickets.take_changes(), (vec![], vec![]));
+
+        tickets.remove(second);
+        assert!(!tickets.is_held(pos));
+        assert_eq!(tickets.take_changes(), (vec![], vec![pos]));
+    }
+
+    #[test]
+    fn released_and_acquired_again_is_not_released() {
+        let mut tickets = ChunkTickets::new();
+        let pos = ChunkPos::new(0, 0);
+
+        let id = tickets.add(TicketKind::Forced, pos, 0, None);
+        tickets.take_changes();
+
+        tickets.remove(id);
+        tickets.add(TicketKind::Forced, pos, 0, None);
+
+        assert_eq!(tickets.take_changes(), (vec![pos], vec![]));
+    }
+
+    #[test]
+    fn move_keeps_overlapping_chunks() {
+        let mut tickets = ChunkTickets::new();
+        let id = tickets.add(TicketKind::Spawn, ChunkPos::new(0, 0), 1, None);
+        tickets.take_changes();
+
+        tickets.move_ticket(id, ChunkPos::new(1, 0), 1);
+        let (acquired, released) = tickets.take_changes();
+
+        assert_eq!(sorted(acquired), [(1, -1), (1, 1), (2, 0)]);
+        assert_eq!(sorted(released), [(-1, 0), (0, -1), (0, 1)]);
+        assert!(tickets.is_held(ChunkPos::new(0, 0)));
+        assert_eq!(tickets.get(id).unwrap().center, ChunkPos::new(1, 0));
+    }
+
+    #[test]
+    fn expired_tickets_are_removed() {
+        let mut tickets = ChunkTickets::new();
+        let now = Instant::now();
+        let pos = ChunkPos::new(0, 0);
+
+        let kept = tickets.add(TicketKind::Forced, pos, 0, None);
+        let expiring = tickets.add(TicketKind::Unsaved, pos, 0, Some(now));
+        let later = tickets.add(
+            TicketKind::Spawn,
+            ChunkPos::new(5, 5),
+            0,
+            Some(now + Duration::from_secs(60)),
+        );
+
+        tickets.expire(now);
+
+        assert!(tickets.get(kept).is_some());
+        assert!(tickets.get(expiring).is_none());
+        assert!(tickets.get(later).is_some());
+        assert!(tickets.is_held(pos));
+
+        tickets.set_expiry(kept, Some(now));
+        tickets.expire(now);
+
+        assert!(!tickets.is_held(pos));
+        assert!(tickets.is_held(ChunkPos::new(5, 5)));
+    }
+}
@@ -0,0 +1,147 @@
+use valence::prelude::{Client, Config};
+
+/// The number of ticks in a Minecraft day.
+pub const TICKS_PER_DAY: i64 = 24000;
+
+/// Clients advance the clock on their own, the server only corrects them once
+/// a second like vanilla does.
+const SYNC_INTERVAL: i64 = 20;
+
+/// The clock of a world.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WorldTime {
+    /// The number of ticks the world has been running for.
+    game_time: i64,
+    /// The time of day in ticks. It is not wrapped at the end of the day, the
+    /// day count is `time_of_day / TICKS_PER_DAY`. It is never negative,
+    /// clients stop their clock for negative times.
+    time_of_day: i64,
+    changed: bool,
+}
+
+impl WorldTime {
+    pub fn new(game_time: i64, time_of_day: i64) -> Self {
+        Self {
+            game_time,
+            time_of_day: time_of_day.max(0),
+            changed: true,
+        }
+    }
+
+    pub fn game_time(&self) -> i64 {
+        self.game_time
+    }
+
+    pub fn time_of_day(&self) -> i64 {
+        self.time_of_day
+    }
+
+    /// The number of days that have passed.
+    pub fn day(&self) -> i64 {
+        self.time_of_day / TICKS_PER_DAY
+    }
+
+    /// Sets the time of day, negative times are clamped to 0.
+    pub fn set_time_of_day(&mut self, time_of_day: i64) {
+        self.time_of_day = time_of_day.max(0);
+        self.changed = true;
+    }
+
+    pub fn add_time_of_day(&mut self, ticks: i64) {
+        self.set_time_of_day(advance(self.time_of_day, ticks));
+    }
+
+    pub(crate) fn tick(&mut self) {
+        self.game_time = self.game_time.wrapping_add(1);
+        self.time_of_day = advance(self.time_of_day, 1);
+
+        if self.game_time % SYNC_INTERVAL == 0 {
+            self.changed = true;
+        }
+    }
+
+    /// Returns `true` if clients have to be told about the time since the
+    /// last call.
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    /// Sends the time to a client.
+    pub fn sync<G: Config>(&self, client: &mut Client<G>) {
+        client.set_time(self.game_time, self.time_of_day);
+    }
+}
+
+/// Adds `ticks` to a time of day. When the day count no longer fits, it starts
+/// over at day 0 at the same time of day.
+fn advance(time_of_day: i64, ticks: i64) -> i64 {
+    time_of_day.checked_add(ticks).unwrap_or_else(|| {
+        (time_of_day % TICKS_PER_DAY + ticks % TICKS_PER_DAY).rem_euclid(TICKS_PER_DAY)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tick_advances_both_clocks() {
+        let mut time = WorldTime::new(10, 100);
+        time.take_changed();
+
+        time.tick();
+
+        assert_eq!(time.game_time(), 11);
+        assert_eq!(time.time_of_day(), 101);
+    }
+
+    #[test]
+    fn clients_are_corrected_every_sync_interval() {
+        let mut time = WorldTime::new(0, 0);
+        assert!(time.take_changed());
+
+        for _ in 1..SYNC_INTERVAL {
+            time.tick();
+            assert!(!time.take_changed());
+        }
+
+        time.tick();
+        assert!(time.take_changed());
+    }
+
+    #[test]
+    fn day_counts_whole_days() {
+        let mut time = WorldTime::new(0, 0);
+
+        time.set_time_of_day(TICKS_PER_DAY * 3 - 1);
+        assert_eq!(time.day(), 2);
+
+        time.add_time_of_day(1);
+        assert_eq!(time.day(), 3);
+        assert!(time.take_changed());
+    }
+
+    #[test]
+    fn negative_times_are_clamped() {
+        let mut time = WorldTime::new(0, -5);
+        assert_eq!(time.time_of_day(), 0);
+
+        time.set_time_of_day(1000);
+        time.add_time_of_day(-2000);
+        assert_eq!(time.time_of_day(), 0);
+    }
+
+    #[test]
+    fn overflow_keeps_time_of_day() {
+        let mut time = WorldTime::new(i64::MAX, i64::MAX);
+        let daytime = i64::MAX % TICKS_PER_DAY;
+
+        time.tick();
+        assert_eq!(time.game_time(), i64::MIN);
+        assert_eq!(time.time_of_day(), (daytime + 1) % TICKS_PER_DAY);
+
+        time.set_time_of_day(i64::MAX);
+        time.add_time_of_day(i64::MAX);
+        assert_eq!(time.time_of_day(), (daytime * 2) % TICKS_PER_DAY);
+    }
+}
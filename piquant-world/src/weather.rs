@@ -0,0 +1,294 @@
+use rand::Rng;
+use valence::prelude::{Client, Config};
+
+/// How fast rain and thunder fade in and out per tick.
+const LEVEL_STEP: f32 = 0.01;
+
+/// Rain is only shown once its level is above this.
+const RAIN_THRESHOLD: f32 = 0.2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WeatherKind {
+    Clear,
+    Rain,
+    Thunder,
+}
+
+/// The weather of a world. Rain and thunder toggle independently after a
+/// random duration, unless a clear spell set by a command is running.
+#[derive(Clone, Debug, Default)]
+pub struct Weather {
+    /// Ticks until the clear spell ends.
+    clear_time: i32,
+    raining: bool,
+    /// Ticks until `raining` toggles.
+    rain_time: i32,
+    thundering: bool,
+    /// Ticks until `thundering` toggles.
+    thunder_time: i32,
+
+    rain_level: f32,
+    thunder_level: f32,
+
+    /// What clients were last told.
+    sent_raining: bool,
+    sent_rain_level: f32,
+    sent_thunder_level: f32,
+}
+
+/// The part of the weather that changed since the last sync.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WeatherChanges {
+    pub raining: Option<bool>,
+    pub rain_level: Option<f32>,
+    pub thunder_level: Option<f32>,
+}
+
+impl Weather {
+    pub fn new(
+        clear_time: i32,
+        raining: bool,
+        rain_time: i32,
+        thundering: bool,
+        thunder_time: i32,
+    ) -> Self {
+        let rain_level = if raining { 1.0 } else { 0.0 };
+        let thunder_level = if raining && thundering { 1.0 } else { 0.0 };
+
+        Self {
+            clear_time,
+            raining,
+            rain_time,
+            thundering,
+            thunder_time,
+            rain_level,
+            thunder_level,
+            ..Default::default()
+        }
+    }
+
+    pub fn kind(&self) -> WeatherKind {
+        match (self.raining, self.thundering) {
+            (true, true) => WeatherKind::Thunder,
+            (true, false) => WeatherKind::Rain,
+            (false, _) => WeatherKind::Clear,
+        }
+    }
+
+    pub fn clear_time(&self) -> i32 {
+        self.clear_time
+    }
+
+    pub fn is_raining(&self) -> bool {
+        self.raining
+    }
+
+    pub fn rain_time(&self) -> i32 {
+        self.rain_time
+    }
+
+    pub fn is_thundering(&self) -> bool {
+        self.thundering
+    }
+
+    pub fn thunder_time(&self) -> i32 {
+        self.thunder_time
+    }
+
+    /// Changes the weather for `duration` ticks, after which it changes
+    /// randomly again.
+    pub fn set(&mut self, kind: WeatherKind, duration: i32) {
+        match kind {
+            WeatherKind::Clear => {
+                self.clear_time = duration;
+                self.rain_time = 0;
+                self.thunder_time = 0;
+            }
+            WeatherKind::Rain | WeatherKind::Thunder => {
+                self.clear_time = 0;
+                self.rain_time = duration;
+                self.thunder_time = duration;
+            }
+        }
+
+        self.raining = kind != WeatherKind::Clear;
+        self.thundering = kind == WeatherKind::Thunder;
+    }
+
+    pub(crate) fn tick(&mut self) {
+        let mut rng = rand::thread_rng();
+
+        if self.clear_time > 0 {
+            self.clear_time -= 1;
+            self.rain_time = if self.raining { 0 } else { 1 };
+            self.thunder_time = if self.thundering { 0 } else { 1 };
+            self.raining = false;
+            self.thundering = false;
+        } else {
+            if self.thunder_time > 0 {
+                self.thunder_time -= 1;
+                if self.thunder_time == 0 {
+                    self.thundering = !self.thundering;
+                }
+            } else if self.thundering {
+                self.thunder_time = rng.gen_range(3600..15600);
+            } else {
+                self.thunder_time = rng.gen_range(12000..180000);
+            }
+
+            if self.rain_time > 0 {
+                self.rain_time -= 1;
+                if self.rain_time == 0 {
+                    self.raining = !self.raining;
+                }
+            } else if self.raining {
+                self.rain_time = rng.gen_range(12000..24000);
+            } else {
+                self.rain_time = rng.gen_range(12000..180000);
+            }
+        }
+
+        let step = |level: f32, up: bool| {
+            let step = if up { LEVEL_STEP } else { -LEVEL_STEP };
+            (level + step).clamp(0.0, 1.0)
+        };
+
+        self.rain_level = step(self.rain_level, self.raining);
+        // Thunder needs rain to be heard.
+        self.thunder_level = step(self.thunder_level, self.raining && self.thundering);
+    }
+
+    /// Returns what clients have to be told since the last call.
+    pub fn take_changes(&mut self) -> WeatherChanges {
+        let mut changes = WeatherChanges::default();
+
+        let raining = self.rain_level > RAIN_THRESHOLD;
+
+        if raining != self.sent_raining {
+            changes.raining = Some(raining);
+            self.sent_raining = raining;
+        }
+
+        if self.rain_level != self.sent_rain_level {
+            changes.rain_level = Some(self.rain_level);
+            self.sent_rain_level = self.rain_level;
+        }
+
+        if self.thunder_level != self.sent_thunder_level {
+            changes.thunder_level = Some(self.thunder_level);
+            self.sent_thunder_level = self.thunder_level;
+        }
+
+        changes
+    }
+
+    /// Sends the whole weather to a client that just joined.
+    pub fn sync<G: Config>(&self, client: &mut Client<G>) {
+        if self.rain_level > RAIN_THRESHOLD {
+            client.set_raining(true);
+            client.set_rain_level(self.rain_level);
+            client.set_thunder_level(self.thunder_level);
+        }
+    }
+}
+
+impl WeatherChanges {
+    pub fn is_empty(&self) -> bool {
+        self.raining.is_none() && self.rain_level.is_none() && self.thunder_level.is_none()
+    }
+
+    pub fn sync<G: Config>(&self, client: &mut Client<G>) {
+        if let Some(raining) = self.raining {
+            client.set_raining(raining);
+        }
+
+        if let Some(rain_level) = self.rain_level {
+            client.set_rain_level(rain_level);
+        }
+
+        if let Some(thunder_level) = self.thunder_level {
+            client.set_thunder_level(thunder_level);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_rain_holds_for_duration() {
+        let mut weather = Weather::default();
+        weather.set(WeatherKind::Rain, 100);
+
+        assert_eq!(weather.kind(), WeatherKind::Rain);
+
+        for _ in 0..99 {
+            weather.tick();
+        }
+        assert!(weather.is_raining());
+
+        weather.tick();
+        assert!(!weather.is_raining());
+    }
+
+    #[test]
+    fn clear_spell_stops_rain_and_thunder() {
+        let mut weather = Weather::new(0, true, 1000, true, 1000);
+        assert_eq!(weather.kind(), WeatherKind::Thunder);
+
+        weather.set(WeatherKind::Clear, 10);
+
+        for _ in 0..10 {
+            weather.tick();
+            assert_eq!(weather.kind(), WeatherKind::Clear);
+        }
+
+        assert_eq!(weather.clear_time(), 0);
+    }
+
+    #[test]
+    fn rain_fades_in_before_it_is_shown() {
+        let mut weather = Weather::default();
+        weather.set(WeatherKind::Rain, 1000);
+
+        let mut level = weather.rain_level;
+        let raining = loop {
+            weather.tick();
+
+            if let Some(raining) = weather.take_changes().raining {
+                break raining;
+            }
+
+            level = weather.rain_level;
+        };
+
+        assert!(raining);
+        assert!(level <= RAIN_THRESHOLD && weather.rain_level > RAIN_THRESHOLD);
+    }
+
+    #[test]
+    fn thunder_needs_rain() {
+        let mut weather = Weather::default();
+        weather.set(WeatherKind::Thunder, 1000);
+        weather.tick();
+
+        let changes = weather.take_changes();
+        assert_eq!(changes.rain_level, Some(LEVEL_STEP));
+        assert_eq!(changes.thunder_level, Some(LEVEL_STEP));
+
+        weather.raining = false;
+        weather.tick();
+
+        assert_eq!(weather.take_changes().thunder_level, Some(0.0));
+    }
+
+    #[test]
+    fn unchanged_weather_sends_nothing() {
+        let mut weather = Weather::default();
+        weather.set(WeatherKind::Clear, 1000);
+        weather.tick();
+
+        assert!(weather.take_changes().is_empty());
+    }
+}
@@ -1,17 +1,25 @@
 use std::{
     collections::{btree_map::Entry, BTreeMap},
     fs::{self, File},
-    io::{self, ErrorKind, Read, Seek, SeekFrom},
+    io::{self, ErrorKind, Read, Seek, SeekFrom, Write},
     path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
-use byteorder::{BigEndian, ReadBytesExt};
-use flate2::bufread::{GzDecoder, ZlibDecoder};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use flate2::{
+    bufread::{GzDecoder, ZlibDecoder},
+    write::{GzEncoder, ZlibEncoder},
+    Compression,
+};
 use thiserror::Error;
-use valence_nbt::Compound;
+use valence_nbt::{compound, Compound, Value};
 use vek::Vec3;
 
-use crate::Seed;
+use crate::{
+    border::WorldBorder, entity_index::EntityIndex, tickets::ChunkTickets, time::WorldTime,
+    weather::Weather, Seed,
+};
 
 const SECTOR_SIZE: usize = 4096;
 
@@ -25,10 +33,28 @@ fn get_region_coords_from_file_name(input: &str) -> (i32, i32) {
 
 pub trait WorldState {
     fn new(world_root: impl Into<PathBuf>) -> Self;
-    fn read_spawnpoint(&mut self) -> Result<(), ReadChunkError>;
+    fn read_level(&mut self) -> Result<(), ReadChunkError>;
+    fn write_level(&self) -> Result<(), WriteChunkError>;
     fn read_all(&mut self) -> Result<(), ReadChunkError>;
     fn read_region(&mut self, region_x: i32, region_z: i32) -> Result<(), ReadChunkError>;
     fn read_chunk(&self, chunk_x: i32, chunk_z: i32) -> Result<Option<AnvilChunk>, ReadChunkError>;
+    fn write_chunk(
+        &mut self,
+        chunk_x: i32,
+        chunk_z: i32,
+        data: &Compound,
+    ) -> Result<(), WriteChunkError>;
+    fn tickets(&self) -> &ChunkTickets;
+    fn tickets_mut(&mut self) -> &mut ChunkTickets;
+    fn time(&self) -> &WorldTime;
+    fn time_mut(&mut self) -> &mut WorldTime;
+    fn weather(&self) -> &Weather;
+    fn weather_mut(&mut self) -> &mut Weather;
+    fn border(&self) -> &WorldBorder;
+    fn border_mut(&mut self) -> &mut WorldBorder;
+
+    fn entities(&self) -> &EntityIndex;
+    fn entities_mut(&mut self) -> &mut EntityIndex;
 }
 
 pub struct PiquantWorld {
@@ -40,6 +66,16 @@ pub struct PiquantWorld {
 
     pub spawn: Option<Vec3<f64>>,
     pub seed: Option<Seed>,
+
+    /// The "Data" compound of level.dat, kept so that fields piquant does not
+    /// know about survive a save.
+    level: Compound,
+
+    tickets: ChunkTickets,
+    time: WorldTime,
+    weather: Weather,
+    border: WorldBorder,
+    entities: EntityIndex,
 }
 
 impl WorldState for PiquantWorld {
@@ -55,14 +91,28 @@ impl WorldState for PiquantWorld {
 
             spawn: None,
             seed: None,
+
+            level: Compound::new(),
+
+            tickets: ChunkTickets::new(),
+            time: WorldTime::default(),
+            weather: Weather::default(),
+            border: WorldBorder::default(),
+            entities: EntityIndex::new(),
         }
     }
 
-    fn read_spawnpoint(&mut self) -> Result<(), ReadChunkError> {
+    /// Reads the spawn point, time and weather from level.dat. A missing
+    /// level.dat is not an error, the world is new.
+    fn read_level(&mut self) -> Result<(), ReadChunkError> {
         let mut level_dat = self.world_root.clone();
         level_dat.push("level.dat");
 
-        let mut file = File::open(level_dat)?;
+        let mut file = match File::open(level_dat) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
         let mut data_buf: Vec<u8> = Vec::new();
         file.read_to_end(&mut data_buf)?;
 
@@ -90,6 +140,63 @@ impl WorldState for PiquantWorld {
             *spawn_z.unwrap() as f64,
         ));
 
+        let long = |name| match level.get(name) {
+            Some(Value::Long(v)) => *v,
+            _ => 0,
+        };
+        let int = |name| match level.get(name) {
+            Some(Value::Int(v)) => *v,
+            _ => 0,
+        };
+        let flag = |name| matches!(level.get(name), Some(Value::Byte(v)) if *v != 0);
+
+        self.time = WorldTime::new(long("Time"), long("DayTime"));
+        self.weather = Weather::new(
+            int("clearWeatherTime"),
+            flag("raining"),
+            int("rainTime"),
+            flag("thundering"),
+            int("thunderTime"),
+        );
+
+        self.level = level.clone();
+
+        Ok(())
+    }
+
+    /// Writes the spawn point, time and weather to level.dat.
+    fn write_level(&self) -> Result<(), WriteChunkError> {
+        let mut level = self.level.clone();
+
+        if let Some(spawn) = self.spawn {
+            level.insert("SpawnX", spawn.x.floor() as i32);
+            level.insert("SpawnY", spawn.y.floor() as i32);
+            level.insert("SpawnZ", spawn.z.floor() as i32);
+        }
+
+        level.insert("Time", self.time.game_time());
+        level.insert("DayTime", self.time.time_of_day());
+        level.insert("clearWeatherTime", self.weather.clear_time());
+        level.insert("raining", self.weather.is_raining());
+        level.insert("rainTime", self.weather.rain_time());
+        level.insert("thundering", self.weather.is_thundering());
+        level.insert("thunderTime", self.weather.thunder_time());
+
+        let nbt = compound! {
+            "Data" => level,
+        };
+
+        fs::create_dir_all(&self.world_root)?;
+
+        // Write next to level.dat first so a crash never leaves it half written.
+        let tmp = self.world_root.join("level.dat_new");
+
+        let mut z = GzEncoder::new(File::create(&tmp)?, Compression::default());
+        valence_nbt::to_binary_writer(&mut z, &nbt, "")?;
+        z.finish()?;
+
+        fs::rename(tmp, self.world_root.join("level.dat"))?;
+
         Ok(())
     }
 
@@ -221,6 +328,140 @@ impl WorldState for PiquantWorld {
 
         Ok(Some(AnvilChunk { data, timestamp }))
     }
+
+    /// Writes a chunk to its region file, creating the file if needed. The
+    /// chunk is rewritten in place when it still fits in its old sectors and
+    /// appended to the end of the file otherwise.
+    fn write_chunk(
+        &mut self,
+        chunk_x: i32,
+        chunk_z: i32,
+        data: &Compound,
+    ) -> Result<(), WriteChunkError> {
+        let region_x = chunk_x.div_euclid(32);
+        let region_z = chunk_z.div_euclid(32);
+
+        let region = match self.regions.entry((region_x, region_z)) {
+            Entry::Vacant(ve) => {
+                fs::create_dir_all(&self.region_root)?;
+
+                let path = self
+                    .region_root
+                    .join(format!("r.{region_x}.{region_z}.mca"));
+
+                let mut file = File::options()
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .truncate(false)
+                    .open(path)?;
+
+                let mut header = [0; SECTOR_SIZE * 2];
+
+                if file.metadata()?.len() >= header.len() as u64 {
+                    file.read_exact(&mut header)?;
+                } else {
+                    file.write_all(&header)?;
+                }
+
+                ve.insert(Region { file, header })
+            }
+            Entry::Occupied(oe) => oe.into_mut(),
+        };
+
+        let mut nbt = vec![];
+        valence_nbt::to_binary_writer(&mut nbt, data, "")?;
+
+        let mut z = ZlibEncoder::new(Vec::new(), Compression::default());
+        z.write_all(&nbt)?;
+        let compressed = z.finish()?;
+
+        // Length, compression scheme and data, padded to whole sectors.
+        let mut buf = Vec::with_capacity(compressed.len() + 5);
+        buf.write_u32::<BigEndian>(compressed.len() as u32 + 1)?;
+        buf.write_u8(2)?;
+        buf.extend_from_slice(&compressed);
+
+        let sector_count = buf.len().div_ceil(SECTOR_SIZE);
+
+        if sector_count > 0xff {
+            return Err(WriteChunkError::ChunkTooLarge);
+        }
+
+        buf.resize(sector_count * SECTOR_SIZE, 0);
+
+        let chunk_idx = (chunk_x.rem_euclid(32) + chunk_z.rem_euclid(32) * 32) as usize;
+
+        let location_bytes = (&region.header[chunk_idx * 4..]).read_u32::<BigEndian>()?;
+        let old_offset = (location_bytes >> 8) as u64;
+        let old_count = (location_bytes & 0xff) as usize;
+
+        let sector_offset = if old_offset >= 2 && old_count >= sector_count {
+            old_offset
+        } else {
+            let len = region.file.metadata()?.len();
+            len.div_ceil(SECTOR_SIZE as u64)
+        };
+
+        region
+            .file
+            .seek(SeekFrom::Start(sector_offset * SECTOR_SIZE as u64))?;
+        region.file.write_all(&buf)?;
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as u32);
+
+        let location = (sector_offset as u32) << 8 | sector_count as u32;
+
+        (&mut region.header[chunk_idx * 4..]).write_u32::<BigEndian>(location)?;
+        (&mut region.header[chunk_idx * 4 + SECTOR_SIZE..]).write_u32::<BigEndian>(timestamp)?;
+
+        region.file.seek(SeekFrom::Start(0))?;
+        region.file.write_all(&region.header)?;
+
+        Ok(())
+    }
+
+    fn tickets(&self) -> &ChunkTickets {
+        &self.tickets
+    }
+
+    fn tickets_mut(&mut self) -> &mut ChunkTickets {
+        &mut self.tickets
+    }
+
+    fn time(&self) -> &WorldTime {
+        &self.time
+    }
+
+    fn time_mut(&mut self) -> &mut WorldTime {
+        &mut self.time
+    }
+
+    fn weather(&self) -> &Weather {
+        &self.weather
+    }
+
+    fn weather_mut(&mut self) -> &mut Weather {
+        &mut self.weather
+    }
+
+    fn border(&self) -> &WorldBorder {
+        &self.border
+    }
+
+    fn border_mut(&mut self) -> &mut WorldBorder {
+        &mut self.border
+    }
+
+    fn entities(&self) -> &EntityIndex {
+        &self.entities
+    }
+
+    fn entities_mut(&mut self) -> &mut EntityIndex {
+        &mut self.entities
+    }
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -249,6 +490,17 @@ pub enum ReadChunkError {
     IncompleteNbtRead,
 }
 
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum WriteChunkError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Nbt(#[from] valence_nbt::Error),
+    #[error("chunk does not fit in 255 sectors")]
+    ChunkTooLarge,
+}
+
 #[derive(Debug)]
 struct Region {
     file: File,
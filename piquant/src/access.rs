@@ -0,0 +1,255 @@
+use std::{collections::HashMap, net::IpAddr};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use time::{format_description::FormatItem, macros::format_description, OffsetDateTime};
+use valence::{prelude::Text, uuid::Uuid};
+
+pub const BANNED_PLAYERS_FILE: &str = "banned-players.json";
+pub const BANNED_IPS_FILE: &str = "banned-ips.json";
+pub const WHITELIST_FILE: &str = "whitelist.json";
+
+/// How vanilla writes dates, e.g. `2023-01-15 14:03:22 +0000`.
+const DATE_FORMAT: &[FormatItem<'static>] = format_description!(
+    "[year]-[month]-[day] [hour]:[minute]:[second] [offset_hour sign:mandatory][offset_minute]"
+);
+
+/// The expiry of bans that don't expire.
+const FOREVER: &str = "forever";
+
+/// What vanilla stores about both kinds of bans.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Ban {
+    pub created: String,
+    /// Who banned, `Server` for the console.
+    pub source: String,
+    /// A date, or `forever`.
+    pub expires: String,
+    pub reason: String,
+}
+
+impl Ban {
+    /// A ban that doesn't expire.
+    pub fn new(source: &str, reason: Option<String>) -> Self {
+        let created = OffsetDateTime::now_utc()
+            .format(DATE_FORMAT)
+            .unwrap_or_default();
+
+        Self {
+            created,
+            source: source.to_string(),
+            expires: FOREVER.to_string(),
+            reason: reason.unwrap_or_else(|| "Banned by an operator.".to_string()),
+        }
+    }
+
+    /// Returns `true` once the expiry date passed. Bans with an expiry that
+    /// isn't a date never expire.
+    pub fn is_expired(&self) -> bool {
+        OffsetDateTime::parse(&self.expires, DATE_FORMAT)
+            .is_ok_and(|expires| expires <= OffsetDateTime::now_utc())
+    }
+
+    /// What a banned player sees when they can't join.
+    pub fn message(&self, headline: &str) -> Text {
+        let mut message = format!("{headline}\nReason: {}", self.reason);
+
+        if self.expires != FOREVER {
+            message += &format!("\nYour ban will be removed on {}", self.expires);
+        }
+
+        message.into()
+    }
+}
+
+/// An entry of the vanilla `banned-players.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BannedPlayer {
+    pub uuid: Uuid,
+    pub name: String,
+    #[serde(flatten)]
+    pub ban: Ban,
+}
+
+/// An entry of the vanilla `banned-ips.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BannedIp {
+    pub ip: IpAddr,
+    #[serde(flatten)]
+    pub ban: Ban,
+}
+
+/// An entry of the vanilla `whitelist.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WhitelistEntry {
+    pub uuid: Uuid,
+    pub name: String,
+}
+
+/// Who may join the server.
+#[derive(Debug, Default)]
+pub struct Access {
+    banned_players: Vec<BannedPlayer>,
+    banned_ips: Vec<BannedIp>,
+    whitelist: Vec<WhitelistEntry>,
+    /// Only players on the whitelist and ops may join.
+    pub whitelist_enabled: bool,
+    /// The players that tried to join since the server started, by their
+    /// name in lowercase, so players that left can still be banned.
+    seen: HashMap<String, (Uuid, String)>,
+}
+
+impl Access {
+    /// Reads `banned-players.json`, `banned-ips.json` and `whitelist.json`,
+    /// which are all optional.
+    pub fn load(whitelist_enabled: bool) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            banned_players: read_list(BANNED_PLAYERS_FILE)?,
+            banned_ips: read_list(BANNED_IPS_FILE)?,
+            whitelist: read_list(WHITELIST_FILE)?,
+            whitelist_enabled,
+            seen: HashMap::new(),
+        })
+    }
+
+    /// Reads the three files again.
+    pub fn reload(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.banned_players = read_list(BANNED_PLAYERS_FILE)?;
+        self.banned_ips = read_list(BANNED_IPS_FILE)?;
+        self.whitelist = read_list(WHITELIST_FILE)?;
+        Ok(())
+    }
+
+    /// Writes all three files.
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        write_list(BANNED_PLAYERS_FILE, &self.banned_players)?;
+        write_list(BANNED_IPS_FILE, &self.banned_ips)?;
+        write_list(WHITELIST_FILE, &self.whitelist)
+    }
+
+    /// Remembers a player that tries to join.
+    pub fn remember(&mut self, uuid: Uuid, name: &str) {
+        self.seen
+            .insert(name.to_lowercase(), (uuid, name.to_string()));
+    }
+
+    /// Finds a player that is not online by name, among the players that
+    /// were seen, banned or whitelisted.
+    pub fn find_player(&self, name: &str) -> Option<(Uuid, String)> {
+        if let Some(player) = self.seen.get(&name.to_lowercase()) {
+            return Some(player.clone());
+        }
+
+        let banned = self
+            .banned_players
+            .iter()
+            .map(|entry| (entry.uuid, &entry.name));
+        let whitelisted = self.whitelist.iter().map(|entry| (entry.uuid, &entry.name));
+
+        banned
+            .chain(whitelisted)
+            .find(|(_, entry)| entry.eq_ignore_ascii_case(name))
+            .map(|(uuid, name)| (uuid, name.clone()))
+    }
+
+    /// The ban of a player, unless it expired.
+    pub fn player_ban(&self, uuid: Uuid) -> Option<&Ban> {
+        self.banned_players
+            .iter()
+            .find(|entry| entry.uuid == uuid && !entry.ban.is_expired())
+            .map(|entry| &entry.ban)
+    }
+
+    /// The ban of an address, unless it expired.
+    pub fn ip_ban(&self, ip: IpAddr) -> Option<&Ban> {
+        self.banned_ips
+            .iter()
+            .find(|entry| entry.ip == ip && !entry.ban.is_expired())
+            .map(|entry| &entry.ban)
+    }
+
+    /// Returns `false` if the player is banned already.
+    pub fn ban_player(&mut self, uuid: Uuid, name: &str, ban: Ban) -> bool {
+        if self.player_ban(uuid).is_some() {
+            return false;
+        }
+
+        self.banned_players.retain(|entry| entry.uuid != uuid);
+        self.banned_players.push(BannedPlayer {
+            uuid,
+            name: name.to_string(),
+            ban,
+        });
+
+        true
+    }
+
+    /// Lifts the ban of a player by name. Returns `false` if no player of that
+    /// name is banned.
+    pub fn pardon_player(&mut self, name: &str) -> bool {
+        let len = self.banned_players.len();
+        self.banned_players
+            .retain(|entry| !entry.name.eq_ignore_ascii_case(name));
+        self.banned_players.len() != len
+    }
+
+    /// Returns `false` if the address is banned already.
+    pub fn ban_ip(&mut self, ip: IpAddr, ban: Ban) -> bool {
+        if self.ip_ban(ip).is_some() {
+            return false;
+        }
+
+        self.banned_ips.retain(|entry| entry.ip != ip);
+        self.banned_ips.push(BannedIp { ip, ban });
+
+        true
+    }
+
+    /// Returns `false` if the address is not banned.
+    pub fn pardon_ip(&mut self, ip: IpAddr) -> bool {
+        let len = self.banned_ips.len();
+        self.banned_ips.retain(|entry| entry.ip != ip);
+        self.banned_ips.len() != len
+    }
+
+    pub fn whitelist(&self) -> &[WhitelistEntry] {
+        &self.whitelist
+    }
+
+    pub fn is_whitelisted(&self, uuid: Uuid) -> bool {
+        self.whitelist.iter().any(|entry| entry.uuid == uuid)
+    }
+
+    /// Returns `false` if the player is on the whitelist already.
+    pub fn whitelist_add(&mut self, uuid: Uuid, name: &str) -> bool {
+        if self.is_whitelisted(uuid) {
+            return false;
+        }
+
+        self.whitelist.push(WhitelistEntry {
+            uuid,
+            name: name.to_string(),
+        });
+
+        true
+    }
+
+    /// Returns `false` if the player was not on the whitelist.
+    pub fn whitelist_remove(&mut self, uuid: Uuid) -> bool {
+        let len = self.whitelist.len();
+        self.whitelist.retain(|entry| entry.uuid != uuid);
+        self.whitelist.len() != len
+    }
+}
+
+fn read_list<T: DeserializeOwned>(file: &str) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+    match std::fs::read_to_string(file) {
+        Ok(contents) => Ok(serde_json::from_str(&contents)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn write_list<T: Serialize>(file: &str, list: &[T]) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::write(file, serde_json::to_string_pretty(list)?)?;
+    Ok(())
+}
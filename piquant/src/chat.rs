@@ -19,6 +19,11 @@ impl MessageQueue {
         self.messages.push_back(message);
     }
 
+    /// Queues a message sent with `/say`.
+    pub fn queue_say(&mut self, name: &str, message: &str) {
+        self.messages.push_back(format!("[{name}] {message}"));
+    }
+
     pub fn pop_front(&mut self) -> Option<String> {
         self.messages.pop_front()
     }
@@ -1,9 +1,50 @@
-use valence::{prelude::EntityId, protocol::ItemStack};
+use piquant_world::LightView;
+use valence::{
+    prelude::{EntityId, Vec3, WorldId},
+    protocol::{BlockPos, ItemStack},
+};
+
+use crate::edit::{Clipboard, History, Selection};
+
+pub const MAX_HEALTH: f32 = 20.0;
+
+/// The inventory slot of the first hotbar slot.
+pub const HOTBAR_START: usize = 36;
+
+/// Where a player respawns after dying, set with `/spawnpoint`.
+#[derive(Clone, Copy, Debug)]
+pub struct SpawnPoint {
+    pub world: WorldId,
+    pub position: BlockPos,
+    pub yaw: f32,
+}
+
+impl SpawnPoint {
+    /// The middle of the block, where players stand.
+    pub fn center(&self) -> Vec3<f64> {
+        Vec3::new(
+            self.position.x as f64 + 0.5,
+            self.position.y as f64,
+            self.position.z as f64 + 0.5,
+        )
+    }
+}
 
 pub struct ClientState {
     pub entity_id: EntityId,
     pub held_item_slot: i16,
     pub creative_mode_slots: Vec<Option<ItemStack>>,
+    pub light_view: LightView,
+    pub health: f32,
+    /// The position at the end of the last tick.
+    pub last_position: Vec3<f64>,
+    pub selection: Selection,
+    pub clipboard: Option<Clipboard>,
+    pub history: History,
+    pub spawn_point: Option<SpawnPoint>,
+    /// Set when the client respawned, the world it respawned in is sent to it
+    /// once it loaded that world.
+    pub respawned: bool,
 }
 
 impl Default for ClientState {
@@ -12,6 +53,36 @@ impl Default for ClientState {
             entity_id: Default::default(),
             held_item_slot: 0,
             creative_mode_slots: Vec::new(),
+            light_view: LightView::default(),
+            health: MAX_HEALTH,
+            last_position: Vec3::zero(),
+            selection: Selection::default(),
+            clipboard: None,
+            history: History::default(),
+            spawn_point: None,
+            respawned: false,
         }
     }
 }
+
+impl ClientState {
+    /// Remembers an item a player in creative mode put in their inventory.
+    pub fn set_creative_mode_slot(&mut self, slot: i16, item: Option<ItemStack>) {
+        let Ok(slot) = usize::try_from(slot) else {
+            return;
+        };
+
+        if slot >= self.creative_mode_slots.len() {
+            self.creative_mode_slots.resize(slot + 1, None);
+        }
+
+        self.creative_mode_slots[slot] = item;
+    }
+
+    /// The item in the selected hotbar slot, as far as the server knows.
+    pub fn held_item(&self) -> Option<&ItemStack> {
+        self.creative_mode_slots
+            .get(HOTBAR_START + self.held_item_slot as usize)?
+            .as_ref()
+    }
+}
@@ -1,107 +1,1867 @@
-use piquant_macros::command;
+use std::{net::IpAddr, task::Poll};
+
+use piquant_command::{
+    Arguments, EntitySelector, FromArgument, Greedy, Origin, Parser, PermissionCheck, Rotation,
+    Time,
+};
+use piquant_macros::{command, FromArgument};
+use piquant_world::{
+    ChunkState, ChunkTickets, EntityTarget, TicketId, TicketKind, WeatherKind, WorldState,
+    TICKS_PER_DAY,
+};
 use valence::{
-    prelude::{Chunk, ChunkPos, Client, Color, World},
-    protocol::{BlockKind, BlockState, TextFormat},
+    prelude::{
+        ChunkPos, Client, ClientId, Color, EntityId, GameMode, Server, Text, Uuid, Vec3, WorldId,
+    },
+    protocol::{BlockPos, BlockState, ItemStack, TextFormat},
 };
 
-use crate::server::Game;
+use crate::{
+    access::Ban,
+    client_state::{SpawnPoint, HOTBAR_START},
+    config::{Config, CONFIG_FILE},
+    edit::{self, Clipboard, Corner, EditKind, EditTask, Region, Selection},
+    selector::Select,
+    sender::CommandSender,
+    server::Game,
+    tasks::{Task, TaskContext, TaskId},
+};
 
 /// A simple test command
 /// * `str`: An optional string to print
 #[command]
-pub fn test(client: Client<Game>, str: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
-    println!("Hello, {}!", client.username());
+pub fn test(
+    #[context(sender)] sender: CommandSender,
+    str: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Hello, {}!", sender.name());
 
     if let Some(str) = str {
-        client.send_message(format!("You tested for: {}", str));
+        sender.send_message(format!("You tested for: {}", str));
     }
 
     Ok(())
 }
 
-#[command]
-pub fn seed(client: Client<Game>, world: World<Game>) -> Result<(), Box<dyn std::error::Error>> {
+#[command(permission = "piquant.command.seed")]
+pub fn seed(
+    #[context(sender)] sender: CommandSender,
+    #[context(world)] world: World<Game>,
+) -> Result<(), Box<dyn std::error::Error>> {
     let seed: u32 = world.state.seed.clone().unwrap().into();
 
-    client.send_message(format!("World Seed: {}", seed));
+    sender.send_message(format!("World Seed: {}", seed));
 
     Ok(())
 }
 
 #[command]
-pub fn some_command(client: Client<Game>, data: String) -> Result<(), Box<dyn std::error::Error>> {
-    client.send_message(format!("You sent: {}", data));
+pub fn some_command(
+    #[context(sender)] sender: CommandSender,
+    data: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    sender.send_message(format!("You sent: {}", data));
 
     Ok(())
 }
 
-#[command]
-pub fn gamemode(client: Client<Game>, gamemode: String) -> Result<(), Box<dyn std::error::Error>> {
-    let new_gamemode = match gamemode.as_str() {
-        "survival" => valence::prelude::GameMode::Survival,
-        "creative" => valence::prelude::GameMode::Creative,
-        "adventure" => valence::prelude::GameMode::Adventure,
-        "spectator" => valence::prelude::GameMode::Spectator,
-        _ => {
-            client.send_message(format!("{} is not a valid gamemode", gamemode).color(Color::RED));
-            return Ok(());
+/// Sends a message to every player
+/// * `message`: The rest of the line
+#[command(permission = "piquant.command.say")]
+pub fn say(
+    #[context(sender)] sender: CommandSender,
+    #[context(server)] server: Server<Game>,
+    message: Greedy,
+) -> Result<(), Box<dyn std::error::Error>> {
+    server
+        .state
+        .message_queue
+        .queue_say(sender.name(), &message.0);
+
+    Ok(())
+}
+
+#[command(permission = "piquant.command.gamemode", aliases = ["gm"])]
+pub fn gamemode(
+    #[context(client)] client: Client<Game>,
+    gamemode: GameMode,
+) -> Result<(), Box<dyn std::error::Error>> {
+    client.set_game_mode(gamemode);
+
+    Ok(())
+}
+
+#[command(permission = "piquant.command.setblock")]
+pub fn setblock(
+    #[context(sender)] sender: CommandSender,
+    #[context(world)] world: World<Game>,
+    pos: BlockPos,
+    #[suggest("blocks")] block: BlockState,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !world
+        .state
+        .border()
+        .contains(pos.x as f64 + 0.5, pos.z as f64 + 0.5)
+    {
+        sender.send_message("Cannot place blocks outside the world border".color(Color::RED));
+        return Ok(());
+    }
+
+    piquant_world::set_block_state(world, pos, block)
+        .ok_or("That position is not loaded or outside of the world")?;
+
+    // if previous == grassblock,
+    //     check if there is grass on top,
+    //         if so, check if the block we replaced it with can grow grass,
+    //             if not, remove grass
+
+    // and there are probably a ton more blocks with "weird" behavior like this.
+    // how do we properly handle/structurize this?
+
+    // same with like chests, check if it's a double-chest, if so, remove the other half.
+
+    Ok(())
+}
+
+/// Selects the first corner of the region to edit
+/// * `pos`: Where the corner is, the block you stand in by default
+#[command(permission = "piquant.command.pos")]
+pub fn pos1(
+    #[context(sender)] sender: CommandSender,
+    #[context(client)] client: Client<Game>,
+    pos: Option<BlockPos>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pos = pos.unwrap_or_else(|| BlockPos::at(client.position().into_array()));
+    sender.send_message(Selection::select(client, Corner::First, pos));
+
+    Ok(())
+}
+
+/// Selects the second corner of the region to edit
+/// * `pos`: Where the corner is, the block you stand in by default
+#[command(permission = "piquant.command.pos")]
+pub fn pos2(
+    #[context(sender)] sender: CommandSender,
+    #[context(client)] client: Client<Game>,
+    pos: Option<BlockPos>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pos = pos.unwrap_or_else(|| BlockPos::at(client.position().into_array()));
+    sender.send_message(Selection::select(client, Corner::Second, pos));
+
+    Ok(())
+}
+
+/// Puts the wand in your hand, it selects corners with a left or right click
+#[command(permission = "piquant.command.pos")]
+pub fn wand(
+    #[context(sender)] sender: CommandSender,
+    #[context(client)] client: Client<Game>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let slot = HOTBAR_START + client.state.held_item_slot as usize;
+    let wand = ItemStack::new(edit::WAND, 1, None);
+
+    client.replace_slot(slot as u16, wand.clone());
+    client.state.set_creative_mode_slot(slot as i16, Some(wand));
+
+    sender.send_message(
+        "Left click a block to select the first corner, right click a block for the second",
+    );
+
+    Ok(())
+}
+
+/// Sets every block of your selection
+#[command(permission = "piquant.command.fill")]
+pub fn fill(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    #[context(client)] client: Client<Game>,
+    #[suggest("blocks")] block: BlockState,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (world, region) = selected_region(client)?;
+    let blocks = region.positions().map(|pos| (pos, block));
+
+    start_edit(game, sender, client, "Fill", EditKind::Edit, world, blocks)
+}
+
+/// Replaces every block of one kind in your selection
+/// * `from`: The kind of block to replace, whatever its properties
+/// * `to`: The block to replace it with
+#[command(permission = "piquant.command.replace")]
+pub fn replace(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    #[context(client)] client: Client<Game>,
+    #[context(world)] world: World<Game>,
+    #[suggest("blocks")] from: BlockState,
+    #[suggest("blocks")] to: BlockState,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (world_id, region) = selected_region(client)?;
+    let blocks: Vec<_> = region
+        .positions()
+        .filter(|pos| {
+            world
+                .chunks
+                .block_state(*pos)
+                .is_some_and(|block| block.to_kind() == from.to_kind())
+        })
+        .map(|pos| (pos, to))
+        .collect();
+
+    start_edit(
+        game,
+        sender,
+        client,
+        "Replace",
+        EditKind::Edit,
+        world_id,
+        blocks,
+    )
+}
+
+/// Sets the outside of your selection and fills the inside with air
+#[command(permission = "piquant.command.hollow")]
+pub fn hollow(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    #[context(client)] client: Client<Game>,
+    #[suggest("blocks")] block: BlockState,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (world, region) = selected_region(client)?;
+    let blocks = region.positions().map(|pos| {
+        if region.is_outside(pos) {
+            (pos, block)
+        } else {
+            (pos, BlockState::AIR)
         }
+    });
+
+    start_edit(
+        game,
+        sender,
+        client,
+        "Hollow",
+        EditKind::Edit,
+        world,
+        blocks,
+    )
+}
+
+/// Sets the four sides of your selection
+#[command(permission = "piquant.command.walls")]
+pub fn walls(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    #[context(client)] client: Client<Game>,
+    #[suggest("blocks")] block: BlockState,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (world, region) = selected_region(client)?;
+    let blocks = region
+        .positions()
+        .filter(|pos| region.is_wall(*pos))
+        .map(|pos| (pos, block));
+
+    start_edit(game, sender, client, "Walls", EditKind::Edit, world, blocks)
+}
+
+#[derive(FromArgument)]
+pub enum CloneMode {
+    Normal,
+    Move,
+}
+
+/// Copies your selection to another place
+/// * `destination`: Where the lowest corner of the copy goes
+/// * `mode`: `move` leaves air behind and selects the copy
+#[command(permission = "piquant.command.clone")]
+pub fn clone(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    #[context(client)] client: Client<Game>,
+    #[context(world)] world: World<Game>,
+    destination: BlockPos,
+    mode: Option<CloneMode>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (world_id, region) = selected_region(client)?;
+    let target = region
+        .moved_to(destination)
+        .ok_or("The copy would be outside the world")?;
+
+    let mut blocks: Vec<_> = region
+        .positions()
+        .filter_map(|pos| {
+            let block = world.chunks.block_state(pos)?;
+            let pos = BlockPos::new(
+                pos.x - region.min.x + target.min.x,
+                pos.y - region.min.y + target.min.y,
+                pos.z - region.min.z + target.min.z,
+            );
+
+            Some((pos, block))
+        })
+        .collect();
+
+    if matches!(mode, Some(CloneMode::Move)) {
+        blocks.extend(
+            region
+                .positions()
+                .filter(|pos| !target.contains(*pos))
+                .map(|pos| (pos, BlockState::AIR)),
+        );
+
+        let selection = &mut client.state.selection;
+        selection.set(world_id, Corner::First, target.min);
+        selection.set(world_id, Corner::Second, target.max);
+    }
+
+    start_edit(
+        game,
+        sender,
+        client,
+        "Clone",
+        EditKind::Edit,
+        world_id,
+        blocks,
+    )
+}
+
+/// Copies your selection, relative to the block you stand in
+#[command(permission = "piquant.command.copy")]
+pub fn copy(
+    #[context(sender)] sender: CommandSender,
+    #[context(client)] client: Client<Game>,
+    #[context(world)] world: World<Game>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (_, region) = selected_region(client)?;
+    let origin = BlockPos::at(client.position().into_array());
+    let clipboard = Clipboard::copy(world, region, origin);
+
+    sender.send_message(format!("Copied {} block(s)", clipboard.len()));
+    client.state.clipboard = Some(clipboard);
+
+    Ok(())
+}
+
+/// Places the blocks you copied, relative to the block you stand in
+/// * `rotation`: How many degrees to turn the blocks clockwise, a multiple of 90
+#[command(permission = "piquant.command.paste")]
+pub fn paste(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    #[context(client)] client: Client<Game>,
+    #[arg(default = 0)] rotation: i32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if rotation % 90 != 0 {
+        return Err("The rotation must be a multiple of 90 degrees".into());
+    }
+
+    let mut clipboard = client
+        .state
+        .clipboard
+        .clone()
+        .ok_or("Your clipboard is empty, /copy a selection first")?;
+    clipboard.rotate(rotation.rem_euclid(360) as u32 / 90);
+
+    let world = client.world();
+    let origin = BlockPos::at(client.position().into_array());
+    let blocks: Vec<_> = clipboard.paste(origin).collect();
+
+    start_edit(game, sender, client, "Paste", EditKind::Edit, world, blocks)
+}
+
+/// Undoes your last edit
+#[command(permission = "piquant.command.undo")]
+pub fn undo(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    #[context(client)] client: Client<Game>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    editor_id(game, sender)?;
+
+    let change = client
+        .state
+        .history
+        .take_undo()
+        .ok_or("There is nothing to undo")?;
+
+    start_edit(
+        game,
+        sender,
+        client,
+        "Undo",
+        EditKind::Undo,
+        change.world,
+        change.blocks,
+    )
+}
+
+/// Redoes the last edit you undid
+#[command(permission = "piquant.command.redo")]
+pub fn redo(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    #[context(client)] client: Client<Game>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    editor_id(game, sender)?;
+
+    let change = client
+        .state
+        .history
+        .take_redo()
+        .ok_or("There is nothing to redo")?;
+
+    start_edit(
+        game,
+        sender,
+        client,
+        "Redo",
+        EditKind::Redo,
+        change.world,
+        change.blocks,
+    )
+}
+
+/// The region a player selected in the world they are in.
+fn selected_region(client: &Client<Game>) -> Result<(WorldId, Region), Box<dyn std::error::Error>> {
+    let world = client.world();
+    let region = client.state.selection.region(world)?;
+    region.check_volume()?;
+
+    Ok((world, region))
+}
+
+/// Sets the blocks chunk by chunk in a task, recording what they replace in
+/// the player's history.
+fn start_edit(
+    game: &Game,
+    sender: &CommandSender,
+    client: &mut Client<Game>,
+    name: &str,
+    kind: EditKind,
+    world: WorldId,
+    blocks: impl IntoIterator<Item = (BlockPos, BlockState)>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let id = editor_id(game, sender)?;
+    let task = EditTask::new(name, kind, world, id, client, blocks);
+
+    game.tasks().spawn(sender, task);
+
+    Ok(())
+}
+
+/// The client of the player that sent the command, once their last edit is
+/// done. A running edit is still recording into the history, so undoing,
+/// redoing or starting another edit meanwhile would mix up what gets undone.
+fn editor_id(game: &Game, sender: &CommandSender) -> Result<ClientId, Box<dyn std::error::Error>> {
+    let id = sender
+        .client_id()
+        .ok_or("Only players can edit the world")?;
+
+    if let Some(running) = game
+        .tasks()
+        .iter()
+        .find(|running| running.task.editor() == Some(id))
+    {
+        return Err(format!(
+            "Wait for your last edit to finish, or stop it with /cancel {}",
+            running.id
+        )
+        .into());
+    }
+
+    Ok(id)
+}
+
+/// Keeps chunks loaded without players nearby
+#[command(permission = "piquant.command.forceload")]
+pub mod forceload {
+    use super::*;
+
+    /// Force loads the chunk at a block position
+    #[subcommand]
+    pub fn add(
+        #[context(sender)] sender: CommandSender,
+        #[context(world)] world: World<Game>,
+        x: i64,
+        z: i64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let pos = ChunkPos::at(x as f64, z as f64);
+        let tickets = world.state.tickets_mut();
+
+        if find_forced(tickets, pos).is_some() {
+            sender.send_message(format!("Chunk ({}, {}) is force loaded", pos.x, pos.z));
+        } else {
+            tickets.add(TicketKind::Forced, pos, 0, None);
+            sender.send_message(format!("Chunk ({}, {}) is now force loaded", pos.x, pos.z));
+        }
+
+        Ok(())
+    }
+
+    /// Stops force loading the chunk at a block position
+    #[subcommand]
+    pub fn remove(
+        #[context(sender)] sender: CommandSender,
+        #[context(world)] world: World<Game>,
+        x: i64,
+        z: i64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let pos = ChunkPos::at(x as f64, z as f64);
+        let tickets = world.state.tickets_mut();
+
+        if let Some(id) = find_forced(tickets, pos) {
+            tickets.remove(id);
+            sender.send_message(format!(
+                "Chunk ({}, {}) is no longer force loaded",
+                pos.x, pos.z
+            ));
+        } else {
+            sender.send_message(format!("Chunk ({}, {}) is not force loaded", pos.x, pos.z));
+        }
+
+        Ok(())
+    }
+
+    /// Tells whether the chunk at a block position is force loaded
+    #[subcommand]
+    pub fn query(
+        #[context(sender)] sender: CommandSender,
+        #[context(world)] world: World<Game>,
+        x: i64,
+        z: i64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let pos = ChunkPos::at(x as f64, z as f64);
+
+        if find_forced(world.state.tickets(), pos).is_some() {
+            sender.send_message(format!("Chunk ({}, {}) is force loaded", pos.x, pos.z));
+        } else {
+            sender.send_message(format!("Chunk ({}, {}) is not force loaded", pos.x, pos.z));
+        }
+
+        Ok(())
+    }
+
+    fn find_forced(tickets: &ChunkTickets, pos: ChunkPos) -> Option<TicketId> {
+        tickets
+            .iter()
+            .find(|(_, ticket)| ticket.kind == TicketKind::Forced && ticket.center == pos)
+            .map(|(id, _)| id)
+    }
+}
+
+/// Sets, adds to or queries the time of day
+#[command(permission = "piquant.command.time")]
+pub mod time {
+    use super::*;
+
+    /// A time of day by name or in ticks.
+    pub enum TimeOfDay {
+        Day,
+        Noon,
+        Night,
+        Midnight,
+        Ticks(Time),
+    }
+
+    impl TimeOfDay {
+        fn ticks(&self) -> i64 {
+            match self {
+                TimeOfDay::Day => 1000,
+                TimeOfDay::Noon => 6000,
+                TimeOfDay::Night => 13000,
+                TimeOfDay::Midnight => 18000,
+                TimeOfDay::Ticks(Time(ticks)) => *ticks,
+            }
+        }
+    }
+
+    impl FromArgument for TimeOfDay {
+        const NAME: &'static str = "Time";
+        const OTHER_WORDS: bool = true;
+
+        fn parser() -> Parser<'static> {
+            Parser::Time
+        }
+
+        fn literals() -> Vec<&'static str> {
+            vec!["day", "noon", "night", "midnight"]
+        }
+
+        fn from_argument(
+            args: &mut Arguments,
+            _: &Origin,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            match args.read_word() {
+                "day" => Ok(TimeOfDay::Day),
+                "noon" => Ok(TimeOfDay::Noon),
+                "night" => Ok(TimeOfDay::Night),
+                "midnight" => Ok(TimeOfDay::Midnight),
+                word => Ok(TimeOfDay::Ticks(word.parse()?)),
+            }
+        }
+    }
+
+    /// Sets the time of day
+    /// * `value`: A number of ticks, day, noon, night or midnight
+    #[subcommand]
+    pub fn set(
+        #[context(sender)] sender: CommandSender,
+        #[context(world)] world: World<Game>,
+        value: TimeOfDay,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let time = world.state.time_mut();
+        time.set_time_of_day(value.ticks());
+        sender.send_message(format!("Set the time to {}", time.time_of_day()));
+
+        Ok(())
+    }
+
+    /// Moves the time of day forward
+    /// * `time`: The time to add, in ticks or with a unit like 1d or 20s
+    #[subcommand]
+    pub fn add(
+        #[context(sender)] sender: CommandSender,
+        #[context(world)] world: World<Game>,
+        time: Time,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Time(ticks) = time;
+
+        let time = world.state.time_mut();
+        time.add_time_of_day(ticks);
+        sender.send_message(format!("Set the time to {}", time.time_of_day()));
+
+        Ok(())
+    }
+
+    /// What `/time query` tells.
+    #[derive(FromArgument)]
+    pub enum TimeQuery {
+        Daytime,
+        Gametime,
+        Day,
+    }
+
+    /// Tells the time of day, the age of the world or the number of days
+    #[subcommand]
+    pub fn query(
+        #[context(sender)] sender: CommandSender,
+        #[context(world)] world: World<Game>,
+        kind: TimeQuery,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let time = world.state.time();
+
+        let value = match kind {
+            TimeQuery::Daytime => time.time_of_day() % TICKS_PER_DAY,
+            TimeQuery::Gametime => time.game_time(),
+            TimeQuery::Day => time.day(),
+        };
+
+        sender.send_message(format!("The time is {}", value));
+
+        Ok(())
+    }
+}
+
+/// Changes the weather
+/// * `kind`: One of clear, rain or thunder
+/// * `duration`: How long the weather lasts in seconds, five minutes by default
+#[command(permission = "piquant.command.weather")]
+pub fn weather(
+    #[context(sender)] sender: CommandSender,
+    #[context(world)] world: World<Game>,
+    #[literal("clear", "rain", "thunder")] kind: String,
+    #[arg(min = 0, max = 1_000_000, default = 300)] duration: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let weather_kind = match kind.as_str() {
+        "rain" => WeatherKind::Rain,
+        "thunder" => WeatherKind::Thunder,
+        _ => WeatherKind::Clear,
     };
 
-    client.set_game_mode(new_gamemode);
+    world
+        .state
+        .weather_mut()
+        .set(weather_kind, (duration * 20) as i32);
+
+    let message = match weather_kind {
+        WeatherKind::Clear => "Set the weather to clear",
+        WeatherKind::Rain => "Set the weather to rain",
+        WeatherKind::Thunder => "Set the weather to rain & thunder",
+    };
+    sender.send_message(message);
 
     Ok(())
 }
 
-#[command]
-pub fn setblock(
-    client: Client<Game>,
-    world: World<Game>,
-    x: i64,
-    y: i64,
-    z: i64,
-    block_type: String,
-) -> Result<(), Box<dyn std::error::Error>> {
-    dbg!(&x, &y, &z, &block_type);
-
-    let chunk_pos = ChunkPos::at(x as f64, z as f64);
-
-    if let Some(chunk) = world.chunks.get_mut(chunk_pos) {
-        let block_x = x.rem_euclid(16);
-        let block_z = z.rem_euclid(16);
-        let block_y = y + 64;
-
-        let block_kind = match BlockKind::from_str(&block_type) {
-            Some(block_kind) => block_kind,
-            None => {
-                client
-                    .send_message(format!("{} is not a valid block", block_type).color(Color::RED));
-                return Ok(());
+/// Changes the world border
+#[command(permission = "piquant.command.worldborder")]
+pub mod worldborder {
+    use super::*;
+
+    /// Tells the current diameter of the world border
+    #[subcommand]
+    pub fn get(
+        #[context(sender)] sender: CommandSender,
+        #[context(world)] world: World<Game>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        sender.send_message(format!(
+            "The world border is currently {:.0} block(s) wide",
+            world.state.border().diameter()
+        ));
+
+        Ok(())
+    }
+
+    /// Resizes the world border
+    /// * `diameter`: The new diameter in blocks
+    /// * `time`: The seconds the resize takes
+    #[subcommand]
+    pub fn set(
+        #[context(sender)] sender: CommandSender,
+        #[context(world)] world: World<Game>,
+        #[arg(min = 1.0, max = piquant_world::MAX_BORDER_DIAMETER)] diameter: f64,
+        #[arg(min = 0, max = i32::MAX, default = 0)] time: i64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let border = world.state.border_mut();
+        border.set_diameter(diameter, time * 20);
+
+        sender.send_message(format!(
+            "Set the world border to {:.1} block(s) wide",
+            border.target_diameter()
+        ));
+
+        Ok(())
+    }
+
+    /// Grows or shrinks the world border
+    /// * `distance`: The blocks to add to the diameter
+    /// * `time`: The seconds the resize takes
+    #[subcommand]
+    pub fn add(
+        #[context(sender)] sender: CommandSender,
+        #[context(world)] world: World<Game>,
+        #[arg(
+            min = -piquant_world::MAX_BORDER_DIAMETER,
+            max = piquant_world::MAX_BORDER_DIAMETER
+        )]
+        distance: f64,
+        #[arg(min = 0, max = i32::MAX, default = 0)] time: i64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let border = world.state.border_mut();
+        border.set_diameter(border.target_diameter() + distance, time * 20);
+
+        sender.send_message(format!(
+            "Set the world border to {:.1} block(s) wide",
+            border.target_diameter()
+        ));
+
+        Ok(())
+    }
+
+    /// Moves the center of the world border
+    #[subcommand]
+    pub fn center(
+        #[context(sender)] sender: CommandSender,
+        #[context(world)] world: World<Game>,
+        #[arg(
+            min = -piquant_world::MAX_BORDER_CENTER,
+            max = piquant_world::MAX_BORDER_CENTER
+        )]
+        x: f64,
+        #[arg(
+            min = -piquant_world::MAX_BORDER_CENTER,
+            max = piquant_world::MAX_BORDER_CENTER
+        )]
+        z: f64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        world.state.border_mut().set_center(x, z);
+        sender.send_message(format!(
+            "Set the center of the world border to {x:.2}, {z:.2}"
+        ));
+
+        Ok(())
+    }
+
+    /// Changes the damage outside of the world border
+    /// * `kind`: The damage per block each second, or the blocks without damage
+    #[subcommand]
+    pub fn damage(
+        #[context(sender)] sender: CommandSender,
+        #[context(world)] world: World<Game>,
+        #[literal("amount", "buffer")] kind: String,
+        #[arg(min = 0.0)] value: f64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let border = world.state.border_mut();
+
+        if kind == "amount" {
+            border.damage_per_block = value;
+            sender.send_message(format!(
+                "Set the world border damage to {value:.2} per block each second"
+            ));
+        } else {
+            border.damage_buffer = value;
+            sender.send_message(format!(
+                "Set the world border damage buffer to {value:.2} block(s)"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Changes when clients start showing the world border warning
+    /// * `kind`: The distance in blocks, or the seconds before a moving border arrives
+    #[subcommand]
+    pub fn warning(
+        #[context(sender)] sender: CommandSender,
+        #[context(world)] world: World<Game>,
+        #[literal("distance", "time")] kind: String,
+        #[arg(min = 0, max = i32::MAX)] value: i64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let border = world.state.border_mut();
+
+        if kind == "distance" {
+            border.set_warning_blocks(value as i32);
+            sender.send_message(format!(
+                "Set the world border warning distance to {value} block(s)"
+            ));
+        } else {
+            border.set_warning_time(value as i32);
+            sender.send_message(format!(
+                "Set the world border warning time to {value} second(s)"
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Adds, removes or lists the tags of entities
+/// * `targets`: The entities to change
+/// * `tag`: The tag to add or remove
+#[command(permission = "piquant.command.tag")]
+pub fn tag(
+    #[context(sender)] sender: CommandSender,
+    #[context(world)] world: World<Game>,
+    targets: EntitySelector,
+    #[literal("add", "remove", "list")] action: String,
+    #[arg(regex = "^[-+._A-Za-z0-9]+$")] tag: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let entities = world.state.entities_mut();
+
+    let targets: Vec<_> = entities
+        .select(
+            &targets,
+            sender.position().unwrap_or([0.0; 3]),
+            sender.uuid(),
+        )
+        .into_iter()
+        .map(|target| target.uuid)
+        .collect();
+
+    if targets.is_empty() {
+        sender.send_message("No entity was found".color(Color::RED));
+        return Ok(());
+    }
+
+    if action == "list" {
+        for uuid in targets {
+            let tags: Vec<_> = entities.tags(uuid).collect();
+            sender.send_message(format!(
+                "{uuid} has {} tag(s): {}",
+                tags.len(),
+                tags.join(", ")
+            ));
+        }
+
+        return Ok(());
+    }
+
+    let tag = tag.ok_or("Expected a tag")?;
+
+    let changed = targets
+        .iter()
+        .filter(|uuid| {
+            if action == "add" {
+                entities.add_tag(**uuid, &tag)
+            } else {
+                entities.remove_tag(**uuid, &tag)
             }
+        })
+        .count();
+
+    if action == "add" {
+        sender.send_message(format!("Added tag '{tag}' to {changed} entities"));
+    } else {
+        sender.send_message(format!("Removed tag '{tag}' from {changed} entities"));
+    }
+
+    Ok(())
+}
+
+/// Teleports entities to a position or to another entity
+#[command(permission = "piquant.command.teleport", aliases = ["tp"])]
+pub mod teleport {
+    use super::*;
+
+    /// Teleports you to a position
+    /// * `location`: Where to teleport you
+    /// * `rotation`: Which way you face, you keep your rotation by default
+    #[overload]
+    pub fn to_location(
+        #[context(game)] game: Game,
+        #[context(sender)] sender: CommandSender,
+        #[context(server)] server: Server<Game>,
+        location: Vec3<f64>,
+        rotation: Option<Rotation>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let targets = sender_only(sender)?;
+        teleport_to_location(game, sender, server, &targets, location, rotation)
+    }
+
+    /// Teleports you to an entity
+    /// * `destination`: The entity to teleport you to
+    #[overload]
+    pub fn to_entity(
+        #[context(game)] game: Game,
+        #[context(sender)] sender: CommandSender,
+        #[context(server)] server: Server<Game>,
+        destination: EntitySelector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let targets = sender_only(sender)?;
+        teleport_to_entity(game, sender, server, &targets, &destination)
+    }
+
+    /// Teleports entities to a position
+    /// * `targets`: The entities to teleport
+    /// * `location`: Where to teleport them
+    /// * `rotation`: Which way they face, they keep their rotation by default
+    #[overload]
+    pub fn targets_to_location(
+        #[context(game)] game: Game,
+        #[context(sender)] sender: CommandSender,
+        #[context(server)] server: Server<Game>,
+        targets: EntitySelector,
+        location: Vec3<f64>,
+        rotation: Option<Rotation>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        teleport_to_location(game, sender, server, &targets, location, rotation)
+    }
+
+    /// Teleports entities to another entity
+    /// * `targets`: The entities to teleport
+    /// * `destination`: The entity to teleport them to
+    #[overload]
+    pub fn targets_to_entity(
+        #[context(game)] game: Game,
+        #[context(sender)] sender: CommandSender,
+        #[context(server)] server: Server<Game>,
+        targets: EntitySelector,
+        destination: EntitySelector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        teleport_to_entity(game, sender, server, &targets, &destination)
+    }
+
+    /// Selects the player that sent the command.
+    fn sender_only(sender: &CommandSender) -> Result<EntitySelector, &'static str> {
+        if sender.uuid().is_none() {
+            return Err("Only players can teleport themselves, name the entities to teleport");
+        }
+
+        Ok(EntitySelector {
+            sender_only: true,
+            ..EntitySelector::default()
+        })
+    }
+
+    fn teleport_to_location(
+        game: &Game,
+        sender: &mut CommandSender,
+        server: &mut Server<Game>,
+        targets: &EntitySelector,
+        location: Vec3<f64>,
+        rotation: Option<Rotation>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let world = sender_world(server, sender)?;
+        let facing = rotation.map(|rotation| (rotation.yaw, rotation.pitch));
+        let teleported = teleport_all(game, sender, server, targets, world, location, facing)?;
+
+        sender.send_message(format!(
+            "Teleported {teleported} to {:.2}, {:.2}, {:.2}",
+            location.x, location.y, location.z
+        ));
+
+        Ok(())
+    }
+
+    fn teleport_to_entity(
+        game: &Game,
+        sender: &mut CommandSender,
+        server: &mut Server<Game>,
+        targets: &EntitySelector,
+        destination: &EntitySelector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (world, position, facing, name) = match find_entities(server, sender, destination)[..] {
+            [] => return Err("No entity was found".into()),
+            [(world, ref target)] => {
+                let entity = &server.entities[target.id];
+                let facing = Some((entity.yaw(), entity.pitch()));
+                (world, entity.position(), facing, target_name(target))
+            }
+            _ => return Err("Only one entity is allowed, but the selector matches more".into()),
         };
 
-        let block_state = BlockState::from_kind(block_kind);
-        println!("{} {} {}", block_x, block_y, block_z);
+        let teleported = teleport_all(game, sender, server, targets, world, position, facing)?;
+        sender.send_message(format!("Teleported {teleported} to {name}"));
+
+        Ok(())
+    }
+
+    /// Teleports the entities `targets` selects, returning who was teleported
+    /// for the reply.
+    fn teleport_all(
+        game: &Game,
+        sender: &CommandSender,
+        server: &mut Server<Game>,
+        targets: &EntitySelector,
+        world: WorldId,
+        position: Vec3<f64>,
+        facing: Option<(f32, f32)>,
+    ) -> Result<String, &'static str> {
+        let targets = find_entities(server, sender, targets);
+
+        for (_, target) in &targets {
+            teleport_entity(game, server, target.id, world, position, facing);
+        }
+
+        match &targets[..] {
+            [] => Err("No entity was found"),
+            [(_, target)] => Ok(target_name(target)),
+            _ => Ok(format!("{} entities", targets.len())),
+        }
+    }
+}
+
+/// Teleports you to the spawn of the main world
+#[command]
+pub fn spawn(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    #[context(server)] server: Server<Game>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let entity = sender
+        .client_id()
+        .and_then(|id| server.clients.get(id))
+        .ok_or("Only players can run this command")?
+        .state
+        .entity_id;
+
+    let (world, spawn) = server
+        .worlds
+        .iter()
+        .next()
+        .and_then(|(id, world)| Some((id, world.state.spawn?)))
+        .ok_or("There is no spawn yet")?;
+
+    teleport_entity(game, server, entity, world, spawn, None);
+    sender.send_message("Teleported to spawn");
+
+    Ok(())
+}
+
+/// Sets where players join and respawn without a spawn point of their own
+/// * `pos`: The new spawn, the block you stand in by default
+#[command(permission = "piquant.command.setworldspawn")]
+pub fn setworldspawn(
+    #[context(sender)] sender: CommandSender,
+    #[context(server)] server: Server<Game>,
+    pos: Option<BlockPos>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let world_id = sender_world(server, sender)?;
+    let pos = pos
+        .or_else(|| sender.position().map(BlockPos::at))
+        .ok_or("Expected a position")?;
+
+    let world = server
+        .worlds
+        .get_mut(world_id)
+        .ok_or("The world no longer exists")?;
+
+    world.state.spawn = Some(Vec3::new(
+        pos.x as f64 + 0.5,
+        pos.y as f64,
+        pos.z as f64 + 0.5,
+    ));
+    world.state.write_level()?;
+
+    // Keep the area around the new spawn loaded instead.
+    let tickets = world.state.tickets_mut();
+    if let Some((id, level)) = tickets
+        .find(&TicketKind::Spawn)
+        .map(|(id, ticket)| (id, ticket.level))
+    {
+        tickets.move_ticket(id, ChunkPos::from(pos), level);
+    }
+
+    for (_, client) in server.clients.iter_mut() {
+        if client.world() == world_id && client.state.spawn_point.is_none() {
+            client.set_spawn_position(pos, 0.0);
+        }
+    }
+
+    sender.send_message(format!(
+        "Set the world spawn point to {}, {}, {}",
+        pos.x, pos.y, pos.z
+    ));
+
+    Ok(())
+}
+
+/// Sets where players respawn after dying
+/// * `targets`: The players, you by default
+/// * `pos`: The spawn point, the block you stand in by default
+/// * `angle`: Which way the players face when they respawn
+#[command(permission = "piquant.command.spawnpoint")]
+pub fn spawnpoint(
+    #[context(sender)] sender: CommandSender,
+    #[context(server)] server: Server<Game>,
+    targets: Option<EntitySelector>,
+    pos: Option<BlockPos>,
+    angle: Option<f32>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let targets = targets.unwrap_or_else(|| EntitySelector {
+        sender_only: true,
+        ..EntitySelector::default()
+    });
+    let point = SpawnPoint {
+        world: sender_world(server, sender)?,
+        position: pos
+            .or_else(|| sender.position().map(BlockPos::at))
+            .ok_or("Expected a position")?,
+        yaw: angle.unwrap_or(0.0),
+    };
+
+    let players: Vec<_> = find_entities(server, sender, &targets)
+        .into_iter()
+        .filter_map(|(_, target)| Some((target.id, target.name?)))
+        .collect();
+
+    for (id, _) in &players {
+        if let Some((_, client)) = server
+            .clients
+            .iter_mut()
+            .find(|(_, client)| client.state.entity_id == *id)
+        {
+            client.state.spawn_point = Some(point);
+            client.set_spawn_position(point.position, point.yaw);
+        }
+    }
+
+    let position = format!(
+        "{}, {}, {}",
+        point.position.x, point.position.y, point.position.z
+    );
+
+    match &players[..] {
+        [] => return Err("No player was found".into()),
+        [(_, name)] => sender.send_message(format!("Set spawn point to {position} for {name}")),
+        _ => sender.send_message(format!(
+            "Set spawn point to {position} for {} players",
+            players.len()
+        )),
+    }
+
+    Ok(())
+}
+
+/// Disconnects players
+/// * `targets`: The players to kick
+/// * `reason`: What the players are told
+#[command(permission = "piquant.command.kick", level = 3)]
+pub fn kick(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    #[context(server)] server: Server<Game>,
+    targets: EntitySelector,
+    reason: Option<Greedy>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let reason = reason.map_or_else(|| "Kicked by an operator".to_string(), |reason| reason.0);
+
+    let kicked: Vec<_> = find_players(game, server, sender, &targets)
+        .into_iter()
+        .filter(|(uuid, _)| disconnect_player(server, *uuid, reason.clone()))
+        .collect();
+
+    if kicked.is_empty() {
+        return Err("No player was found".into());
+    }
+
+    for (_, name) in kicked {
+        sender.send_message(format!("Kicked {name}: {reason}"));
+    }
+
+    Ok(())
+}
+
+/// Keeps players from joining and kicks them if they are online
+/// * `targets`: The players to ban, online or seen since the server started
+/// * `reason`: What the players are told when they try to join
+#[command(permission = "piquant.command.ban", level = 3)]
+pub fn ban(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    #[context(server)] server: Server<Game>,
+    targets: EntitySelector,
+    reason: Option<Greedy>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let players = find_players(game, server, sender, &targets);
+
+    if players.is_empty() {
+        return Err("That player does not exist".into());
+    }
+
+    let reason = reason.map(|reason| reason.0);
+    let mut access = game.access_mut();
+    let mut banned = Vec::new();
+
+    for (uuid, name) in players {
+        let ban = Ban::new(sender.name(), reason.clone());
+        let message = format!("Banned {name}: {}", ban.reason);
+
+        if access.ban_player(uuid, &name, ban) {
+            sender.send_message(message);
+            banned.push(uuid);
+        } else {
+            sender.send_message(
+                format!("Nothing changed. {name} is already banned").color(Color::RED),
+            );
+        }
+    }
 
-        let previous = chunk.set_block_state(
-            block_x as usize,
-            block_y as usize,
-            block_z as usize,
-            block_state,
+    access.save()?;
+    drop(access);
+
+    for uuid in banned {
+        disconnect_player(server, uuid, "You are banned from this server.");
+    }
+
+    Ok(())
+}
+
+/// Keeps an IP address from joining and kicks the players using it
+/// * `target`: An IP address, or the name of a player who is online
+/// * `reason`: What the players are told when they try to join
+#[command(permission = "piquant.command.ban-ip", level = 3, aliases = ["ban-ip"])]
+pub fn banip(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    #[context(server)] server: Server<Game>,
+    target: String,
+    reason: Option<Greedy>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let ip = match target.parse::<IpAddr>() {
+        Ok(ip) => ip,
+        Err(_) => server
+            .clients
+            .iter()
+            .find(|(_, client)| client.username().as_str().eq_ignore_ascii_case(&target))
+            .map(|(_, client)| client.ip())
+            .ok_or("Invalid IP address or unknown player")?,
+    };
+
+    let ban = Ban::new(sender.name(), reason.map(|reason| reason.0));
+    let message = format!("Banned IP {ip}: {}", ban.reason);
+
+    let mut access = game.access_mut();
+
+    if !access.ban_ip(ip, ban) {
+        return Err("Nothing changed. That IP is already banned".into());
+    }
+
+    access.save()?;
+    drop(access);
+
+    sender.send_message(message);
+
+    for (_, client) in server.clients.iter_mut() {
+        if client.ip() == ip {
+            client.disconnect("Your IP address is banned from this server.");
+            sender.send_message(format!("Kicked {}", client.username()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Lets a banned player join again
+/// * `target`: The name of the banned player
+#[command(permission = "piquant.command.pardon", level = 3)]
+pub fn pardon(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    target: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut access = game.access_mut();
+
+    if !access.pardon_player(&target) {
+        return Err("Nothing changed. The player isn't banned".into());
+    }
+
+    access.save()?;
+    sender.send_message(format!("Unbanned {target}"));
+
+    Ok(())
+}
+
+/// Lets a banned IP address join again
+/// * `target`: The banned IP address
+#[command(permission = "piquant.command.pardon-ip", level = 3, aliases = ["pardon-ip"])]
+pub fn pardonip(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    target: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let ip: IpAddr = target.parse().map_err(|_| "Invalid IP address")?;
+    let mut access = game.access_mut();
+
+    if !access.pardon_ip(ip) {
+        return Err("Nothing changed. That IP isn't banned".into());
+    }
+
+    access.save()?;
+    sender.send_message(format!("Unbanned IP {ip}"));
+
+    Ok(())
+}
+
+/// Manages who may join while the whitelist is on
+#[command(permission = "piquant.command.whitelist", level = 3)]
+pub mod whitelist {
+    use super::*;
+
+    /// Lets players join while the whitelist is on
+    /// * `targets`: The players to add, online or seen since the server started
+    #[subcommand]
+    pub fn add(
+        #[context(game)] game: Game,
+        #[context(sender)] sender: CommandSender,
+        #[context(server)] server: Server<Game>,
+        targets: EntitySelector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let players = find_players(game, server, sender, &targets);
+
+        if players.is_empty() {
+            return Err("That player does not exist".into());
+        }
+
+        let mut access = game.access_mut();
+
+        for (uuid, name) in players {
+            if access.whitelist_add(uuid, &name) {
+                sender.send_message(format!("Added {name} to the whitelist"));
+            } else {
+                sender.send_message(
+                    format!("Nothing changed. {name} is already whitelisted").color(Color::RED),
+                );
+            }
+        }
+
+        access.save()
+    }
+
+    /// Keeps players from joining while the whitelist is on
+    /// * `targets`: The players to remove
+    #[subcommand]
+    pub fn remove(
+        #[context(game)] game: Game,
+        #[context(sender)] sender: CommandSender,
+        #[context(server)] server: Server<Game>,
+        targets: EntitySelector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let players = find_players(game, server, sender, &targets);
+
+        if players.is_empty() {
+            return Err("That player does not exist".into());
+        }
+
+        let mut access = game.access_mut();
+
+        for (uuid, name) in players {
+            if access.whitelist_remove(uuid) {
+                sender.send_message(format!("Removed {name} from the whitelist"));
+            } else {
+                sender.send_message(
+                    format!("Nothing changed. {name} is not whitelisted").color(Color::RED),
+                );
+            }
+        }
+
+        access.save()
+    }
+
+    /// Lists the players on the whitelist
+    #[subcommand]
+    pub fn list(
+        #[context(game)] game: Game,
+        #[context(sender)] sender: CommandSender,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let access = game.access();
+        let names: Vec<_> = access
+            .whitelist()
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect();
+
+        if names.is_empty() {
+            sender.send_message("There are no whitelisted players");
+        } else {
+            sender.send_message(format!(
+                "There are {} whitelisted player(s): {}",
+                names.len(),
+                names.join(", ")
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Only lets players on the whitelist and ops join
+    #[subcommand]
+    pub fn on(
+        #[context(game)] game: Game,
+        #[context(sender)] sender: CommandSender,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        set_enabled(game, true)?;
+        sender.send_message("Whitelist is now turned on");
+
+        Ok(())
+    }
+
+    /// Lets everyone join who isn't banned
+    #[subcommand]
+    pub fn off(
+        #[context(game)] game: Game,
+        #[context(sender)] sender: CommandSender,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        set_enabled(game, false)?;
+        sender.send_message("Whitelist is now turned off");
+
+        Ok(())
+    }
+
+    /// Reads the whitelist and the bans again
+    #[subcommand]
+    pub fn reload(
+        #[context(game)] game: Game,
+        #[context(sender)] sender: CommandSender,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        game.access_mut().reload()?;
+        sender.send_message("Reloaded the whitelist");
+
+        Ok(())
+    }
+
+    /// Turns the whitelist on or off, in `server.toml` as well.
+    fn set_enabled(game: &Game, enabled: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let mut access = game.access_mut();
+
+        if access.whitelist_enabled == enabled {
+            let state = if enabled { "on" } else { "off" };
+            return Err(format!("Whitelist is already turned {state}").into());
+        }
+
+        access.whitelist_enabled = enabled;
+
+        let mut config = Config::load(CONFIG_FILE)?;
+        config.network.whitelist = enabled;
+        config.save(CONFIG_FILE)
+    }
+}
+
+/// Gives players every permission up to an op level
+/// * `targets`: The players to make ops, online or seen since the server started
+/// * `level`: The op level, 4 by default
+#[command(permission = "piquant.command.op", level = 3)]
+pub fn op(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    #[context(server)] server: Server<Game>,
+    targets: EntitySelector,
+    #[arg(min = 1, max = 4, default = 4)] level: i32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let players = find_players(game, server, sender, &targets);
+
+    if players.is_empty() {
+        return Err("That player does not exist".into());
+    }
+
+    let mut permissions = game.permissions_mut();
+
+    for (uuid, name) in players {
+        if permissions.set_op(uuid, &name, level) {
+            sender.send_message(format!("Made {name} a server operator"));
+        } else {
+            sender.send_message(
+                format!("Nothing changed. {name} already is an operator").color(Color::RED),
+            );
+        }
+    }
+
+    permissions.save_ops()
+}
+
+/// Takes away the op level of players
+/// * `targets`: The players that are ops
+#[command(permission = "piquant.command.deop", level = 3)]
+pub fn deop(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    #[context(server)] server: Server<Game>,
+    targets: EntitySelector,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let players = find_players(game, server, sender, &targets);
+
+    if players.is_empty() {
+        return Err("That player does not exist".into());
+    }
+
+    let mut permissions = game.permissions_mut();
+
+    for (uuid, name) in players {
+        if permissions.remove_op(uuid) {
+            sender.send_message(format!("Made {name} no longer a server operator"));
+        } else {
+            sender.send_message(
+                format!("Nothing changed. {name} is not an operator").color(Color::RED),
+            );
+        }
+    }
+
+    permissions.save_ops()
+}
+
+/// Lists the players who are online
+#[command]
+pub fn list(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    #[context(server)] server: Server<Game>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let names: Vec<_> = server
+        .clients
+        .iter()
+        .map(|(_, client)| client.username().to_string())
+        .collect();
+
+    sender.send_message(format!(
+        "There are {} of a max of {} players online: {}",
+        names.len(),
+        game.config().network.max_players,
+        names.join(", ")
+    ));
+
+    Ok(())
+}
+
+/// The UUIDs and names of the players a selector matches in any world, with
+/// distances measured as if the sender was in each of them. A name that
+/// matches no one online is looked up among the players that were seen,
+/// banned, whitelisted or made ops before.
+fn find_players(
+    game: &Game,
+    server: &Server<Game>,
+    sender: &CommandSender,
+    targets: &EntitySelector,
+) -> Vec<(Uuid, String)> {
+    let online: Vec<_> = find_entities(server, sender, targets)
+        .into_iter()
+        .filter_map(|(_, target)| Some((target.uuid, target.name?)))
+        .collect();
+
+    if !online.is_empty() {
+        return online;
+    }
+
+    let access = game.access();
+    let permissions = game.permissions();
+
+    targets
+        .names
+        .iter()
+        .filter(|filter| !filter.negated)
+        .filter_map(|filter| {
+            access.find_player(&filter.value).or_else(|| {
+                permissions
+                    .ops()
+                    .iter()
+                    .find(|op| op.name.eq_ignore_ascii_case(&filter.value))
+                    .map(|op| (op.uuid, op.name.clone()))
+            })
+        })
+        .collect()
+}
+
+/// The entities a selector matches in any world and the worlds they are in,
+/// with distances measured as if the sender was in each of them.
+fn find_entities(
+    server: &Server<Game>,
+    sender: &CommandSender,
+    targets: &EntitySelector,
+) -> Vec<(WorldId, EntityTarget)> {
+    let origin = sender.position().unwrap_or([0.0; 3]);
+
+    server
+        .worlds
+        .iter()
+        .flat_map(|(id, world)| {
+            world
+                .state
+                .entities()
+                .select(targets, origin, sender.uuid())
+                .into_iter()
+                .map(move |target| (id, target.clone()))
+        })
+        .collect()
+}
+
+/// The name of a player or the type of another entity.
+fn target_name(target: &EntityTarget) -> String {
+    target.name.clone().unwrap_or_else(|| target.kind.clone())
+}
+
+/// The world of the sender, or the first world if it has none.
+fn sender_world(server: &Server<Game>, sender: &CommandSender) -> Result<WorldId, &'static str> {
+    sender
+        .world()
+        .or_else(|| server.worlds.iter().next().map(|(id, _)| id))
+        .ok_or("There is no world")
+}
+
+/// Moves an entity into `world` at `position`, respawning players that come
+/// from another world. Without a rotation the entity keeps facing the way it
+/// did.
+fn teleport_entity(
+    game: &Game,
+    server: &mut Server<Game>,
+    id: EntityId,
+    world: WorldId,
+    position: Vec3<f64>,
+    rotation: Option<(f32, f32)>,
+) {
+    let player = server
+        .clients
+        .iter()
+        .find(|(_, client)| client.state.entity_id == id)
+        .map(|(client_id, client)| (client_id, client.world()));
+
+    if let Some((client_id, client_world)) = player {
+        if client_world != world {
+            game.change_world(server, client_id, world);
+        }
+
+        if let Some(client) = server.clients.get_mut(client_id) {
+            let (yaw, pitch) = rotation.unwrap_or((client.yaw(), client.pitch()));
+            client.teleport(position, yaw, pitch);
+        }
+    }
+
+    if let Some(entity) = server.entities.get_mut(id) {
+        entity.set_world(world);
+        entity.set_position(position);
+
+        if let Some((yaw, pitch)) = rotation {
+            entity.set_yaw(yaw);
+            entity.set_head_yaw(yaw);
+            entity.set_pitch(pitch);
+        }
+    }
+}
+
+/// Disconnects a player. Returns `false` if they are not online.
+fn disconnect_player(server: &mut Server<Game>, uuid: Uuid, reason: impl Into<Text>) -> bool {
+    match server
+        .clients
+        .iter_mut()
+        .find(|(_, client)| client.uuid() == uuid)
+    {
+        Some((_, client)) => {
+            client.disconnect(reason);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Manages who may run which commands
+#[command(permission = "piquant.command.permissions", level = 3)]
+pub mod permissions {
+    use super::*;
+
+    /// Reads ops.json and permissions.toml again
+    #[subcommand]
+    pub fn reload(
+        #[context(game)] game: Game,
+        #[context(sender)] sender: CommandSender,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        *game.permissions_mut() = crate::permissions::Permissions::load()?;
+        sender.send_message("Reloaded permissions");
+
+        Ok(())
+    }
+}
+
+/// Lists the commands you may run, or explains one of them
+/// * `command`: A command, or the page of the list
+#[command(aliases = ["?"])]
+pub fn help(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    command: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for line in game.commands().help(game, sender, command.as_deref())? {
+        sender.send_message(line);
+    }
+
+    Ok(())
+}
+
+/// Saves the worlds and stops the server
+#[command(permission = "piquant.command.stop", level = 4)]
+pub fn stop(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+) -> Result<(), Box<dyn std::error::Error>> {
+    sender.send_message("Stopping the server");
+    game.stop();
+
+    Ok(())
+}
+
+/// Saves the chunks that changed, spread over the next ticks
+#[command(permission = "piquant.command.save", level = 4, aliases = ["save-all"])]
+pub fn save(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    #[context(server)] server: Server<Game>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for (id, world) in server.worlds.iter() {
+        let chunks: Vec<_> = world
+            .chunks
+            .iter()
+            .filter(|(_, chunk)| chunk.state.is_dirty())
+            .map(|(pos, _)| pos)
+            .collect();
+
+        game.tasks().spawn(
+            sender,
+            SaveTask {
+                world: id,
+                chunks,
+                next: 0,
+                saved: 0,
+            },
         );
+    }
 
-        // if previous == grassblock,
-        //     check if there is grass on top,
-        //         if so, check if the block we replaced it with can grow grass,
-        //             if not, remove grass
+    sender.send_message("Saving the game");
 
-        // and there are probably a ton more blocks with "weird" behavior like this.
-        // how do we properly handle/structurize this?
+    Ok(())
+}
 
-        // same with like chests, check if it's a double-chest, if so, remove the other half.
+/// Writes the chunks that changed when `/save` ran, then level.dat.
+struct SaveTask {
+    world: WorldId,
+    chunks: Vec<ChunkPos>,
+    next: usize,
+    saved: usize,
+}
 
-        dbg!(previous);
+impl Task for SaveTask {
+    fn name(&self) -> &str {
+        "Saving the world"
     }
 
+    fn poll(&mut self, ctx: &mut TaskContext) -> Result<Poll<()>, Box<dyn std::error::Error>> {
+        while let Some(pos) = self.chunks.get(self.next) {
+            self.next += 1;
+
+            let world = ctx
+                .server
+                .worlds
+                .get_mut(self.world)
+                .ok_or("The world no longer exists")?;
+
+            if ctx.game.world().save_chunk(world, *pos)? {
+                self.saved += 1;
+            }
+
+            if ctx.should_yield() {
+                return Ok(Poll::Pending);
+            }
+        }
+
+        if let Some(world) = ctx.server.worlds.get(self.world) {
+            world.state.write_level()?;
+        }
+
+        ctx.sender
+            .send_message(format!("Saved the world, {} chunk(s) changed", self.saved));
+
+        Ok(Poll::Ready(()))
+    }
+
+    fn progress(&self) -> Option<f32> {
+        Some(self.next as f32 / self.chunks.len().max(1) as f32)
+    }
+}
+
+/// Stops a task started by a command like /save
+/// * `task`: The number shown when the task reports its progress, your latest task by default
+#[command]
+pub fn cancel(
+    #[context(game)] game: Game,
+    #[context(sender)] sender: CommandSender,
+    #[context(server)] server: Server<Game>,
+    #[arg(min = 1)] task: Option<i64>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let may_cancel_others = game.has_permission(sender, Some("piquant.command.cancel.others"), 3);
+
+    let mut tasks = game.tasks();
+
+    let id = match task {
+        Some(id) => TaskId::try_from(id).map_err(|_| format!("There is no task {id}"))?,
+        None => tasks
+            .iter()
+            .filter(|task| task.sender.is_same(sender))
+            .last()
+            .map(|task| task.id)
+            .ok_or("You have no running tasks")?,
+    };
+
+    let owned = tasks
+        .iter()
+        .find(|task| task.id == id)
+        .map(|task| task.sender.is_same(sender))
+        .ok_or_else(|| format!("There is no task {id}"))?;
+
+    if !owned && !may_cancel_others {
+        return Err("You can only cancel your own tasks".into());
+    }
+
+    let Some(mut cancelled) = tasks.cancel(id) else {
+        return Err(format!("There is no task {id}").into());
+    };
+    drop(tasks);
+
+    let name = cancelled.task.name().to_string();
+
+    if !owned {
+        cancelled
+            .sender
+            .send_message(format!("{name} was cancelled by {}", sender.name()).color(Color::RED));
+        game.deliver(server, &mut cancelled.sender);
+    }
+
+    sender.send_message(format!("Cancelled {name}"));
+
     Ok(())
 }
@@ -1,14 +1,20 @@
-use std::io::Read;
+use std::{collections::HashMap, io::Read};
 
 use serde::{Deserialize, Serialize};
 
 use piquant_world::SeedType;
 
+pub const CONFIG_FILE: &str = "server.toml";
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Config {
     pub network: Network,
     pub world: World,
     pub gameplay: Gameplay,
+    /// Commands that run other command lines, e.g. `day = "time set day"`.
+    /// Words typed after an alias are appended to its command line.
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -16,6 +22,9 @@ pub struct Network {
     pub port: u16,
     pub max_players: usize,
     pub description: String,
+    /// Only players on the whitelist and ops may join.
+    #[serde(default)]
+    pub whitelist: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -24,6 +33,32 @@ pub struct WorldSpawn {
     pub z: i32,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct WorldBorder {
+    pub center_x: f64,
+    pub center_z: f64,
+    pub diameter: f64,
+    pub damage_per_block: f64,
+    pub damage_buffer: f64,
+    pub warning_blocks: i32,
+    pub warning_time: i32,
+}
+
+impl Default for WorldBorder {
+    fn default() -> Self {
+        Self {
+            center_x: 0.0,
+            center_z: 0.0,
+            diameter: piquant_world::MAX_BORDER_DIAMETER,
+            damage_per_block: 0.2,
+            damage_buffer: 5.0,
+            warning_blocks: 5,
+            warning_time: 15,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct World {
     pub seed: SeedType,
@@ -31,6 +66,8 @@ pub struct World {
     pub view_distance: u8,
     pub chunk_unload_delay: u64,
     pub spawn: WorldSpawn,
+    #[serde(default)]
+    pub border: WorldBorder,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -45,6 +82,7 @@ impl Default for Config {
                 port: 25565,
                 max_players: 16,
                 description: "§bHello Piquant!".into(),
+                whitelist: false,
             },
             world: World {
                 seed: SeedType::FromString("".to_string()),
@@ -52,10 +90,12 @@ impl Default for Config {
                 view_distance: 8,
                 chunk_unload_delay: 30,
                 spawn: WorldSpawn { x: 0, z: 0 },
+                border: WorldBorder::default(),
             },
             gameplay: Gameplay {
                 gamemode: "survival".into(),
             },
+            aliases: HashMap::new(),
         }
     }
 }
@@ -71,11 +111,16 @@ impl Config {
 
     pub fn create(filename: &str) -> Result<Self, Box<dyn std::error::Error>> {
         let settings = Self::default();
-        let toml = toml::to_string(&settings)?;
-        std::fs::write(filename, toml)?;
+        settings.save(filename)?;
         Ok(settings)
     }
 
+    pub fn save(&self, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let toml = toml::to_string(self)?;
+        std::fs::write(filename, toml)?;
+        Ok(())
+    }
+
     pub fn load(filename: &str) -> Result<Self, Box<dyn std::error::Error>> {
         let file = std::fs::File::open(filename)?;
         let mut reader = std::io::BufReader::new(file);
@@ -0,0 +1,160 @@
+use std::{
+    io::Write,
+    sync::{
+        mpsc::{self, Receiver},
+        Arc, Mutex, RwLock,
+    },
+};
+
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    validate::Validator, Context, Editor, ExternalPrinter, Helper,
+};
+
+use crate::server::Commands;
+
+const HISTORY_FILE: &str = ".console_history";
+
+/// Reads commands from stdin on its own thread. Everything printed through the
+/// console, including the log, is put above the line being typed.
+pub struct Console {
+    printer: Mutex<Option<Box<dyn ExternalPrinter + Send>>>,
+    lines: Mutex<Receiver<String>>,
+}
+
+impl Console {
+    pub fn start(commands: Arc<RwLock<Commands>>) -> Result<Arc<Self>, Box<dyn std::error::Error>> {
+        let (lines_tx, lines) = mpsc::channel();
+        let (printer_tx, printer) = mpsc::sync_channel(1);
+
+        std::thread::Builder::new()
+            .name("console".into())
+            .spawn(move || {
+                let mut editor = match Editor::<ConsoleHelper>::new() {
+                    Ok(editor) => editor,
+                    Err(e) => {
+                        eprintln!("Console unavailable: {e}");
+                        let _ = printer_tx.send(None);
+                        return;
+                    }
+                };
+
+                let printer = editor
+                    .create_external_printer()
+                    .ok()
+                    .map(|p| Box::new(p) as Box<dyn ExternalPrinter + Send>);
+                let _ = printer_tx.send(printer);
+
+                editor.set_helper(Some(ConsoleHelper { commands }));
+                let _ = editor.load_history(HISTORY_FILE);
+
+                loop {
+                    match editor.readline("> ") {
+                        Ok(line) => {
+                            let line = line.trim();
+                            if line.is_empty() {
+                                continue;
+                            }
+
+                            editor.add_history_entry(line);
+                            if lines_tx.send(line.to_string()).is_err() {
+                                break;
+                            }
+                        }
+                        Err(ReadlineError::Interrupted) => continue,
+                        Err(_) => break,
+                    }
+
+                    let _ = editor.save_history(HISTORY_FILE);
+                }
+            })?;
+
+        Ok(Arc::new(Self {
+            printer: Mutex::new(printer.recv()?),
+            lines: Mutex::new(lines),
+        }))
+    }
+
+    /// Prints a line without clobbering the input.
+    pub fn print(&self, line: impl std::fmt::Display) {
+        let line = format!("{line}\n");
+
+        match self.printer.lock().unwrap().as_mut() {
+            Some(printer) => {
+                let _ = printer.print(line);
+            }
+            None => print!("{line}"),
+        }
+    }
+
+    /// Makes writers for the log, to pass to `with_writer`.
+    pub fn log_writer(self: &Arc<Self>) -> impl Fn() -> LogWriter {
+        let console = self.clone();
+        move || LogWriter {
+            console: console.clone(),
+            buf: Vec::new(),
+        }
+    }
+
+    /// The lines entered since the last call.
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.lock().unwrap().try_iter().collect()
+    }
+}
+
+/// Writes log output through the console.
+pub struct LogWriter {
+    console: Arc<Console>,
+    buf: Vec<u8>,
+}
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if !self.buf.is_empty() {
+            let line = String::from_utf8_lossy(&self.buf);
+            self.console.print(line.trim_end());
+            self.buf.clear();
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for LogWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Completes the names and literal arguments of commands.
+struct ConsoleHelper {
+    commands: Arc<RwLock<Commands>>,
+}
+
+impl Completer for ConsoleHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.commands.read().unwrap().complete(&line[..pos]))
+    }
+}
+
+impl Hinter for ConsoleHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ConsoleHelper {}
+
+impl Validator for ConsoleHelper {}
+
+impl Helper for ConsoleHelper {}
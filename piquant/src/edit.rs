@@ -0,0 +1,413 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    task::Poll,
+};
+
+use valence::{
+    prelude::{ChunkPos, Client, ClientId, PropName, PropValue, Text, World as MCWorld, WorldId},
+    protocol::{BlockPos, BlockState, ItemKind},
+};
+
+use crate::{
+    server::Game,
+    tasks::{Task, TaskContext},
+};
+
+/// The most blocks a single edit may change.
+pub const MAX_VOLUME: u64 = 1 << 21;
+
+/// How many edits a player can undo.
+pub const HISTORY_LIMIT: usize = 20;
+
+/// Selects the first corner on left click and the second on right click.
+pub const WAND: ItemKind = ItemKind::WoodenAxe;
+
+/// A box of blocks, both corners included.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Region {
+    pub min: BlockPos,
+    pub max: BlockPos,
+}
+
+impl Region {
+    /// The region between two opposite corners.
+    pub fn new(a: BlockPos, b: BlockPos) -> Self {
+        Self {
+            min: BlockPos::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: BlockPos::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+
+    /// The number of blocks along each axis, which can be more than an `i32`
+    /// holds for corners far apart.
+    pub fn size(&self) -> [i64; 3] {
+        let len = |min: i32, max: i32| max as i64 - min as i64 + 1;
+
+        [
+            len(self.min.x, self.max.x),
+            len(self.min.y, self.max.y),
+            len(self.min.z, self.max.z),
+        ]
+    }
+
+    pub fn volume(&self) -> u64 {
+        self.size()
+            .iter()
+            .fold(1, |volume: u64, &len| volume.saturating_mul(len as u64))
+    }
+
+    pub fn contains(&self, pos: BlockPos) -> bool {
+        (self.min.x..=self.max.x).contains(&pos.x)
+            && (self.min.y..=self.max.y).contains(&pos.y)
+            && (self.min.z..=self.max.z).contains(&pos.z)
+    }
+
+    /// Returns `true` for the blocks on the four sides.
+    pub fn is_wall(&self, pos: BlockPos) -> bool {
+        pos.x == self.min.x || pos.x == self.max.x || pos.z == self.min.z || pos.z == self.max.z
+    }
+
+    /// Returns `true` for the blocks on the sides, the floor and the ceiling.
+    pub fn is_outside(&self, pos: BlockPos) -> bool {
+        self.is_wall(pos) || pos.y == self.min.y || pos.y == self.max.y
+    }
+
+    /// The same region moved so that its lowest corner is at `min`, or `None`
+    /// if its highest corner would be past the edge of the world.
+    pub fn moved_to(&self, min: BlockPos) -> Option<Self> {
+        let [x, y, z] = self.size();
+        let end = |min: i32, len: i64| i32::try_from(min as i64 + len - 1).ok();
+
+        Some(Self {
+            min,
+            max: BlockPos::new(end(min.x, x)?, end(min.y, y)?, end(min.z, z)?),
+        })
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = BlockPos> {
+        let Self { min, max } = *self;
+
+        (min.y..=max.y).flat_map(move |y| {
+            (min.z..=max.z).flat_map(move |z| (min.x..=max.x).map(move |x| BlockPos::new(x, y, z)))
+        })
+    }
+
+    /// Fails if an edit of the region would change more than [`MAX_VOLUME`]
+    /// blocks.
+    pub fn check_volume(&self) -> Result<(), String> {
+        if self.volume() > MAX_VOLUME {
+            return Err(format!(
+                "Too many blocks in the selection (maximum {MAX_VOLUME}, selected {})",
+                self.volume()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Corner {
+    First,
+    Second,
+}
+
+/// The corners a player selected with `/pos1`, `/pos2` or the wand.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Selection {
+    world: Option<WorldId>,
+    pos1: Option<BlockPos>,
+    pos2: Option<BlockPos>,
+}
+
+impl Selection {
+    /// Sets a corner. Selecting in another world forgets the other corner.
+    pub fn set(&mut self, world: WorldId, corner: Corner, pos: BlockPos) {
+        if self.world != Some(world) {
+            *self = Self {
+                world: Some(world),
+                ..Self::default()
+            };
+        }
+
+        match corner {
+            Corner::First => self.pos1 = Some(pos),
+            Corner::Second => self.pos2 = Some(pos),
+        }
+    }
+
+    /// The selected region, if both corners were selected in `world`.
+    pub fn region(&self, world: WorldId) -> Result<Region, &'static str> {
+        match (self.pos1, self.pos2) {
+            _ if self.world.is_some_and(|id| id != world) => {
+                Err("Your selection is in another world")
+            }
+            (Some(pos1), Some(pos2)) => Ok(Region::new(pos1, pos2)),
+            _ => Err("Select both corners first, with /pos1 and /pos2 or the wand"),
+        }
+    }
+
+    /// Sets a corner of the client's selection in the world the client is in
+    /// and describes the selection for the player.
+    pub fn select(client: &mut Client<Game>, corner: Corner, pos: BlockPos) -> Text {
+        let world = client.world();
+        let selection = &mut client.state.selection;
+        selection.set(world, corner, pos);
+
+        let name = match corner {
+            Corner::First => "First",
+            Corner::Second => "Second",
+        };
+        let mut message = format!("{name} position set to {}, {}, {}", pos.x, pos.y, pos.z);
+
+        if let Ok(region) = selection.region(world) {
+            message += &format!(" ({} blocks)", region.volume());
+        }
+
+        message.into()
+    }
+}
+
+/// Blocks copied relative to the position of the player that copied them.
+#[derive(Clone, Default, Debug)]
+pub struct Clipboard {
+    blocks: Vec<([i32; 3], BlockState)>,
+}
+
+impl Clipboard {
+    /// Copies the loaded blocks of a region.
+    pub fn copy(world: &MCWorld<Game>, region: Region, origin: BlockPos) -> Self {
+        let blocks = region
+            .positions()
+            .filter_map(|pos| {
+                let block = world.chunks.block_state(pos)?;
+                Some((
+                    [pos.x - origin.x, pos.y - origin.y, pos.z - origin.z],
+                    block,
+                ))
+            })
+            .collect();
+
+        Self { blocks }
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Turns the blocks clockwise around the origin, as seen from above,
+    /// along with the direction they face.
+    pub fn rotate(&mut self, quarter_turns: u32) {
+        for _ in 0..quarter_turns % 4 {
+            for ([x, _, z], block) in &mut self.blocks {
+                (*x, *z) = (-*z, *x);
+                *block = rotate_block(*block);
+            }
+        }
+    }
+
+    /// The blocks placed relative to `origin`.
+    pub fn paste(&self, origin: BlockPos) -> impl Iterator<Item = (BlockPos, BlockState)> + '_ {
+        self.blocks.iter().map(move |&([x, y, z], block)| {
+            (
+                BlockPos::new(origin.x + x, origin.y + y, origin.z + z),
+                block,
+            )
+        })
+    }
+}
+
+/// Turns a block a quarter clockwise by changing where it faces, like
+/// stairs, or the axis it lies along, like logs.
+fn rotate_block(block: BlockState) -> BlockState {
+    let mut rotated = block;
+
+    if let Some(facing) = block.get(PropName::Facing) {
+        rotated = rotated.set(PropName::Facing, rotate_direction(facing));
+    }
+
+    match block.get(PropName::Axis) {
+        Some(PropValue::X) => rotated.set(PropName::Axis, PropValue::Z),
+        Some(PropValue::Z) => rotated.set(PropName::Axis, PropValue::X),
+        _ => rotated,
+    }
+}
+
+fn rotate_direction(value: PropValue) -> PropValue {
+    match value {
+        PropValue::North => PropValue::East,
+        PropValue::East => PropValue::South,
+        PropValue::South => PropValue::West,
+        PropValue::West => PropValue::North,
+        value => value,
+    }
+}
+
+pub type EditId = u64;
+
+/// Why blocks are set, which decides where the blocks they replace are
+/// recorded.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EditKind {
+    Edit,
+    Undo,
+    Redo,
+}
+
+/// The blocks an edit replaced, which is all it takes to undo it.
+#[derive(Debug)]
+pub struct Change {
+    id: EditId,
+    pub world: WorldId,
+    pub blocks: Vec<(BlockPos, BlockState)>,
+}
+
+/// The edits a player can undo and redo.
+#[derive(Default, Debug)]
+pub struct History {
+    next_id: EditId,
+    undo: VecDeque<Change>,
+    redo: Vec<Change>,
+}
+
+impl History {
+    /// Starts recording the blocks an edit replaces. A new edit can't be
+    /// redone after and clears the redo list, and the oldest edit is
+    /// forgotten once more than [`HISTORY_LIMIT`] could be undone.
+    pub fn begin(&mut self, kind: EditKind, world: WorldId) -> EditId {
+        self.next_id += 1;
+
+        let change = Change {
+            id: self.next_id,
+            world,
+            blocks: Vec::new(),
+        };
+
+        match kind {
+            EditKind::Edit => {
+                self.redo.clear();
+                self.push_undo(change);
+            }
+            EditKind::Undo => self.redo.push(change),
+            EditKind::Redo => self.push_undo(change),
+        }
+
+        self.next_id
+    }
+
+    fn push_undo(&mut self, change: Change) {
+        self.undo.push_back(change);
+
+        while self.undo.len() > HISTORY_LIMIT {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Adds the blocks a running edit replaced, unless the edit was forgotten.
+    pub fn record(&mut self, id: EditId, blocks: Vec<(BlockPos, BlockState)>) {
+        if let Some(change) = self
+            .undo
+            .iter_mut()
+            .chain(&mut self.redo)
+            .find(|change| change.id == id)
+        {
+            change.blocks.extend(blocks);
+        }
+    }
+
+    pub fn take_undo(&mut self) -> Option<Change> {
+        self.undo.pop_back()
+    }
+
+    pub fn take_redo(&mut self) -> Option<Change> {
+        self.redo.pop()
+    }
+}
+
+/// Sets blocks one chunk per step, over as many ticks as it takes, and
+/// records the blocks it replaced in the history of the player that started
+/// it. Cancelling the task keeps the chunks it changed so far, which can
+/// still be undone.
+pub struct EditTask {
+    name: String,
+    world: WorldId,
+    client: ClientId,
+    id: EditId,
+    /// The blocks to set grouped by chunk, the next chunk last.
+    chunks: Vec<Vec<(BlockPos, BlockState)>>,
+    total: usize,
+    changed: usize,
+}
+
+impl EditTask {
+    /// An edit of `world` by the player with the client `id`.
+    pub fn new(
+        name: impl Into<String>,
+        kind: EditKind,
+        world: WorldId,
+        id: ClientId,
+        client: &mut Client<Game>,
+        blocks: impl IntoIterator<Item = (BlockPos, BlockState)>,
+    ) -> Self {
+        let mut chunks: BTreeMap<ChunkPos, Vec<_>> = BTreeMap::new();
+
+        for (pos, block) in blocks {
+            chunks
+                .entry(ChunkPos::from(pos))
+                .or_default()
+                .push((pos, block));
+        }
+
+        let chunks: Vec<_> = chunks.into_values().rev().collect();
+
+        Self {
+            name: name.into(),
+            world,
+            client: id,
+            id: client.state.history.begin(kind, world),
+            total: chunks.len(),
+            chunks,
+            changed: 0,
+        }
+    }
+}
+
+impl Task for EditTask {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn poll(&mut self, ctx: &mut TaskContext) -> Result<Poll<()>, Box<dyn std::error::Error>> {
+        while let Some(blocks) = self.chunks.pop() {
+            let world = ctx
+                .server
+                .worlds
+                .get_mut(self.world)
+                .ok_or("The world no longer exists")?;
+
+            let replaced = piquant_world::set_block_states(world, blocks);
+            self.changed += replaced.len();
+
+            if let Some(client) = ctx.server.clients.get_mut(self.client) {
+                client.state.history.record(self.id, replaced);
+            }
+
+            if ctx.should_yield() {
+                return Ok(Poll::Pending);
+            }
+        }
+
+        ctx.sender
+            .send_message(format!("{}: {} block(s) changed", self.name, self.changed));
+
+        Ok(Poll::Ready(()))
+    }
+
+    fn progress(&self) -> Option<f32> {
+        Some(1.0 - self.chunks.len() as f32 / self.total.max(1) as f32)
+    }
+
+    fn editor(&self) -> Option<ClientId> {
+        Some(self.client)
+    }
+}
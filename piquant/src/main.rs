@@ -1,11 +1,21 @@
+mod access;
 mod chat;
 
 mod client_state;
 mod commands;
 mod config;
+mod console;
+mod edit;
+mod permissions;
+mod selector;
+mod sender;
 mod server;
 mod server_state;
+mod tasks;
+use access::Access;
 use config::Config;
+use console::Console;
+use permissions::Permissions;
 use server::Game;
 use server_state::ServerState;
 
@@ -29,11 +39,20 @@ impl From<Box<dyn std::error::Error + Send + Sync>> for Error {
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    tracing_subscriber::fmt().init();
+    let settings = Config::load_or_create(config::CONFIG_FILE)?;
 
-    let settings = Config::load_or_create("server.toml")?;
+    let permissions = Permissions::load()?;
+    let access = Access::load(settings.network.whitelist)?;
 
-    valence::start_server(Game::new(settings), ServerState::new())?;
+    let mut game = Game::new(settings, permissions, access)?;
+
+    let console = Console::start(game.shared_commands())?;
+    tracing_subscriber::fmt()
+        .with_writer(console.log_writer())
+        .init();
+    game.set_console(console);
+
+    valence::start_server(game, ServerState::new())?;
 
     Ok(())
 }
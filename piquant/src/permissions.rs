@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use valence::uuid::Uuid;
+
+pub const OPS_FILE: &str = "ops.json";
+pub const PERMISSIONS_FILE: &str = "permissions.toml";
+
+/// An entry of the vanilla `ops.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Op {
+    pub uuid: Uuid,
+    pub name: String,
+    pub level: i32,
+    #[serde(default)]
+    pub bypasses_player_limit: bool,
+}
+
+/// Permission nodes of players and groups, from `permissions.toml`:
+///
+/// ```toml
+/// [groups.builders]
+/// permissions = ["piquant.command.setblock", "piquant.command.time"]
+///
+/// [players.Steve]
+/// groups = ["builders"]
+/// permissions = ["piquant.command.weather"]
+/// ```
+///
+/// Players are listed by name or UUID. A node ending in `*` grants every node
+/// starting with what comes before it.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct PermissionConfig {
+    pub groups: HashMap<String, Group>,
+    pub players: HashMap<String, PlayerPermissions>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Group {
+    pub permissions: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct PlayerPermissions {
+    pub groups: Vec<String>,
+    pub permissions: Vec<String>,
+}
+
+/// Op levels and permission nodes of all players.
+#[derive(Debug, Default)]
+pub struct Permissions {
+    ops: Vec<Op>,
+    config: PermissionConfig,
+}
+
+impl Permissions {
+    /// Reads `ops.json` and `permissions.toml`, both are optional.
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let ops = match std::fs::read_to_string(OPS_FILE) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let config = match std::fs::read_to_string(PERMISSIONS_FILE) {
+            Ok(contents) => toml::from_str(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => PermissionConfig::default(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self { ops, config })
+    }
+
+    /// Writes the ops to `ops.json`.
+    pub fn save_ops(&self) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(OPS_FILE, serde_json::to_string_pretty(&self.ops)?)?;
+        Ok(())
+    }
+
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+
+    /// Makes a player an op at `level`. Returns `false` if they already were
+    /// an op at that level.
+    pub fn set_op(&mut self, uuid: Uuid, name: &str, level: i32) -> bool {
+        match self.ops.iter_mut().find(|op| op.uuid == uuid) {
+            Some(op) if op.level == level => false,
+            Some(op) => {
+                op.level = level;
+                true
+            }
+            None => {
+                self.ops.push(Op {
+                    uuid,
+                    name: name.to_string(),
+                    level,
+                    bypasses_player_limit: false,
+                });
+                true
+            }
+        }
+    }
+
+    /// Returns `false` if the player was not an op.
+    pub fn remove_op(&mut self, uuid: Uuid) -> bool {
+        let len = self.ops.len();
+        self.ops.retain(|op| op.uuid != uuid);
+        self.ops.len() != len
+    }
+
+    /// The op level of a player, 0 if they are not an op.
+    pub fn op_level(&self, uuid: Uuid) -> i32 {
+        self.ops
+            .iter()
+            .find(|op| op.uuid == uuid)
+            .map_or(0, |op| op.level)
+    }
+
+    /// Returns `true` if the player has the node directly or through a group.
+    pub fn has_node(&self, uuid: Uuid, name: &str, node: &str) -> bool {
+        let player = self
+            .config
+            .players
+            .get(name)
+            .or_else(|| self.config.players.get(&uuid.to_string()));
+
+        let Some(player) = player else {
+            return false;
+        };
+
+        let groups = player
+            .groups
+            .iter()
+            .filter_map(|group| self.config.groups.get(group))
+            .flat_map(|group| &group.permissions);
+
+        player
+            .permissions
+            .iter()
+            .chain(groups)
+            .any(|granted| node_matches(granted, node))
+    }
+
+    /// Returns `true` if the player has the node or at least the op level.
+    pub fn allows(&self, uuid: Uuid, name: &str, node: Option<&str>, op_level: i32) -> bool {
+        if op_level > 0 && self.op_level(uuid) >= op_level {
+            return true;
+        }
+
+        node.is_some_and(|node| self.has_node(uuid, name, node))
+    }
+}
+
+fn node_matches(granted: &str, node: &str) -> bool {
+    match granted.strip_suffix('*') {
+        Some(prefix) => node.starts_with(prefix),
+        None => granted == node,
+    }
+}
@@ -0,0 +1,103 @@
+use piquant_command::{EntitySelector, SelectorTarget, Sort};
+use piquant_world::{EntityIndex, EntityTarget};
+use rand::seq::SliceRandom;
+use valence::{prelude::GameMode, uuid::Uuid, vek::Aabb};
+
+/// An indexed entity as seen by selectors.
+struct Target<'a>(&'a EntityTarget);
+
+impl SelectorTarget for Target<'_> {
+    fn uuid(&self) -> Uuid {
+        self.0.uuid
+    }
+
+    fn kind(&self) -> &str {
+        &self.0.kind
+    }
+
+    fn position(&self) -> [f64; 3] {
+        self.0.position
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.0.name.as_deref()
+    }
+
+    fn game_mode(&self) -> Option<GameMode> {
+        self.0.game_mode
+    }
+
+    fn has_tag(&self, tag: &str) -> bool {
+        self.0.tags.contains(tag)
+    }
+
+    fn has_any_tag(&self) -> bool {
+        !self.0.tags.is_empty()
+    }
+}
+
+/// Resolves selectors against the entities of a world.
+pub trait Select {
+    /// The entities a selector sent by an entity at `origin` matches.
+    fn select(
+        &self,
+        selector: &EntitySelector,
+        origin: [f64; 3],
+        sender: Option<Uuid>,
+    ) -> Vec<&EntityTarget>;
+}
+
+impl Select for EntityIndex {
+    fn select(
+        &self,
+        selector: &EntitySelector,
+        origin: [f64; 3],
+        sender: Option<Uuid>,
+    ) -> Vec<&EntityTarget> {
+        let origin = selector.origin(origin);
+
+        // Only visit the part of the index the distance and volume allow.
+        let mut bounds = Aabb {
+            min: [f64::NEG_INFINITY; 3].into(),
+            max: [f64::INFINITY; 3].into(),
+        };
+
+        if let Some(max) = selector.max_distance {
+            bounds = bounds.intersection(Aabb {
+                min: origin.map(|v| v - max).into(),
+                max: origin.map(|v| v + max).into(),
+            });
+        }
+
+        if let Some((min, max)) = selector.volume(origin) {
+            bounds = bounds.intersection(Aabb {
+                min: min.into(),
+                max: max.into(),
+            });
+        }
+
+        let mut matches: Vec<_> = self
+            .query(bounds)
+            .into_iter()
+            .filter(|entity| selector.matches(&Target(entity), origin, sender))
+            .collect();
+
+        let distance = |entity: &EntityTarget| {
+            let [x, y, z] = entity.position;
+            (x - origin[0]).powi(2) + (y - origin[1]).powi(2) + (z - origin[2]).powi(2)
+        };
+
+        match selector.sort {
+            Sort::Arbitrary => {}
+            Sort::Nearest => matches.sort_by(|a, b| distance(a).total_cmp(&distance(b))),
+            Sort::Furthest => matches.sort_by(|a, b| distance(b).total_cmp(&distance(a))),
+            Sort::Random => matches.shuffle(&mut rand::thread_rng()),
+        }
+
+        if let Some(limit) = selector.limit {
+            matches.truncate(limit);
+        }
+
+        matches
+    }
+}
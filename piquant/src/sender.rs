@@ -0,0 +1,144 @@
+use piquant_command::CommandContext;
+use valence::prelude::{Client, ClientId, Server, Text, Uuid, WorldId};
+
+use crate::server::Game;
+
+/// The eye height of a standing player.
+const PLAYER_EYE_HEIGHT: f64 = 1.62;
+
+/// Who runs a command.
+#[derive(Clone, Debug)]
+pub enum SenderKind {
+    Player {
+        id: ClientId,
+        uuid: Uuid,
+        name: String,
+    },
+    /// The server console, which may run every command.
+    Console,
+}
+
+/// The sender of a command and the replies it gets once the command ran.
+#[derive(Clone, Debug)]
+pub struct CommandSender {
+    kind: SenderKind,
+    position: Option<[f64; 3]>,
+    /// Yaw and pitch.
+    rotation: Option<(f32, f32)>,
+    world: Option<WorldId>,
+    replies: Vec<Text>,
+}
+
+impl CommandSender {
+    fn new(kind: SenderKind) -> Self {
+        Self {
+            kind,
+            position: None,
+            rotation: None,
+            world: None,
+            replies: Vec::new(),
+        }
+    }
+
+    /// A player, positioned where the client is now.
+    pub fn player(id: ClientId, client: &Client<Game>) -> Self {
+        Self::new(SenderKind::Player {
+            id,
+            uuid: client.uuid(),
+            name: client.username().to_string(),
+        })
+        .at(client.world(), client.position().into_array())
+        .with_rotation(client.yaw(), client.pitch())
+    }
+
+    pub fn console() -> Self {
+        Self::new(SenderKind::Console)
+    }
+
+    /// Places the sender in a world, relative coordinates resolve against
+    /// the position.
+    pub fn at(mut self, world: WorldId, position: [f64; 3]) -> Self {
+        self.world = Some(world);
+        self.position = Some(position);
+        self
+    }
+
+    pub fn with_rotation(mut self, yaw: f32, pitch: f32) -> Self {
+        self.rotation = Some((yaw, pitch));
+        self
+    }
+
+    pub fn kind(&self) -> &SenderKind {
+        &self.kind
+    }
+
+    /// The name shown to others, `Server` for the console like in vanilla.
+    pub fn name(&self) -> &str {
+        match &self.kind {
+            SenderKind::Player { name, .. } => name,
+            SenderKind::Console => "Server",
+        }
+    }
+
+    /// Returns `true` if both are the same player or the console, wherever
+    /// they are.
+    pub fn is_same(&self, other: &CommandSender) -> bool {
+        match (&self.kind, &other.kind) {
+            (SenderKind::Player { uuid: a, .. }, SenderKind::Player { uuid: b, .. }) => a == b,
+            (SenderKind::Console, SenderKind::Console) => true,
+            _ => false,
+        }
+    }
+
+    /// The client of a player sender.
+    pub fn client_id(&self) -> Option<ClientId> {
+        match self.kind {
+            SenderKind::Player { id, .. } => Some(id),
+            _ => None,
+        }
+    }
+
+    /// The UUID of a player sender.
+    pub fn uuid(&self) -> Option<Uuid> {
+        match self.kind {
+            SenderKind::Player { uuid, .. } => Some(uuid),
+            _ => None,
+        }
+    }
+
+    pub fn position(&self) -> Option<[f64; 3]> {
+        self.position
+    }
+
+    pub fn rotation(&self) -> Option<(f32, f32)> {
+        self.rotation
+    }
+
+    /// How far the eyes of the sender are above its position, 0 for senders
+    /// that aren't players.
+    pub fn eye_height(&self) -> f64 {
+        match self.kind {
+            SenderKind::Player { .. } if self.position.is_some() => PLAYER_EYE_HEIGHT,
+            _ => 0.0,
+        }
+    }
+
+    pub fn world(&self) -> Option<WorldId> {
+        self.world
+    }
+
+    /// Queues a message for the sender, delivered after the command ran.
+    pub fn send_message(&mut self, message: impl Into<Text>) {
+        self.replies.push(message.into());
+    }
+
+    pub fn take_replies(&mut self) -> Vec<Text> {
+        std::mem::take(&mut self.replies)
+    }
+}
+
+impl CommandContext for CommandSender {
+    type Game = Game;
+    type Sender = CommandSender;
+    type Server = Server<Game>;
+}
@@ -1,48 +1,332 @@
 use std::{
+    collections::HashMap,
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
     path::PathBuf,
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    },
 };
 
 use async_trait::async_trait;
 
-use piquant_command::CommandService;
-use piquant_world::{PiquantWorld, World, WorldState};
+use piquant_command::{CommandContext, CommandService, PermissionCheck, SuggestionContext};
+use piquant_world::{
+    EntityTarget, LightView, PiquantWorld, TicketKind, World, WorldBorder, WorldState, VIEW_PADDING,
+};
 
 use valence::{
     prelude::{World as MCWorld, *},
-    protocol::VarInt,
+    protocol::{packets::s2c::play::BlockUpdate, VarInt},
     server::{Server, SharedServer},
 };
 
-use crate::{client_state::ClientState, commands, server_state::ServerState};
+use crate::{
+    access::Access,
+    client_state::{ClientState, MAX_HEALTH},
+    console::Console,
+    edit::{self, Corner, Selection},
+    permissions::Permissions,
+    sender::{CommandSender, SenderKind},
+    server_state::ServerState,
+    tasks::{Tasks, TASK_BUDGET},
+};
+
+pub type Commands = CommandService<Game, CommandSender, Server<Game>>;
 
 pub struct Game {
     player_count: AtomicUsize,
     world: World<Game>,
     config: crate::config::Config,
-    commands: CommandService<Game, Client<Game>, MCWorld<Game>>,
+    commands: Arc<RwLock<Commands>>,
+    console: Option<Arc<Console>>,
+    /// Set by `/stop`, the server shuts down at the end of the tick.
+    stopping: AtomicBool,
+    permissions: RwLock<Permissions>,
+    /// Set when permissions changed and clients need new command graphs.
+    permissions_changed: AtomicBool,
+    /// Commands that take longer than a tick.
+    tasks: Mutex<Tasks>,
+    access: RwLock<Access>,
 }
 
 impl Game {
-    pub fn new(config: crate::config::Config) -> Self {
+    pub fn new(
+        config: crate::config::Config,
+        permissions: Permissions,
+        access: Access,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
         let world = World::new(
             config.world.seed.clone().into(),
             config.world.chunk_unload_delay,
         );
 
-        let mut commands = CommandService::new();
+        let mut commands = CommandService::with_registered()?;
 
-        commands.add_command(commands::test_def(), commands::test);
-        commands.add_command(commands::seed_def(), commands::seed);
-        commands.add_command(commands::gamemode_def(), commands::gamemode);
-        commands.add_command(commands::setblock_def(), commands::setblock);
+        for (name, command_line) in &config.aliases {
+            commands.add_expansion(name.clone(), command_line.clone())?;
+        }
 
-        Self {
+        Ok(Self {
             player_count: AtomicUsize::new(0),
             world,
             config,
+            commands: Arc::new(RwLock::new(commands)),
+            console: None,
+            stopping: AtomicBool::new(false),
+            permissions: RwLock::new(permissions),
+            permissions_changed: AtomicBool::new(false),
+            tasks: Mutex::new(Tasks::default()),
+            access: RwLock::new(access),
+        })
+    }
+
+    /// The generator and chunk loader of the worlds.
+    pub fn world(&self) -> &World<Game> {
+        &self.world
+    }
+
+    pub fn commands(&self) -> RwLockReadGuard<'_, Commands> {
+        self.commands.read().unwrap()
+    }
+
+    /// Adds or removes commands, clients that can see the changed commands
+    /// get new command graphs at the end of the tick.
+    pub fn commands_mut(&self) -> RwLockWriteGuard<'_, Commands> {
+        self.commands.write().unwrap()
+    }
+
+    /// The commands, for the console to complete them on its own thread.
+    pub fn shared_commands(&self) -> Arc<RwLock<Commands>> {
+        self.commands.clone()
+    }
+
+    /// Runs the commands typed into the console every tick.
+    pub fn set_console(&mut self, console: Arc<Console>) {
+        self.console = Some(console);
+    }
+
+    /// Shuts the server down at the end of the tick.
+    pub fn stop(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+    }
+
+    pub fn permissions(&self) -> RwLockReadGuard<'_, Permissions> {
+        self.permissions.read().unwrap()
+    }
+
+    /// Changes permissions and sends every client the commands they may run
+    /// now on the next tick.
+    pub fn permissions_mut(&self) -> RwLockWriteGuard<'_, Permissions> {
+        self.permissions_changed.store(true, Ordering::SeqCst);
+        self.permissions.write().unwrap()
+    }
+
+    pub fn config(&self) -> &crate::config::Config {
+        &self.config
+    }
+
+    /// The bans and the whitelist.
+    pub fn access(&self) -> RwLockReadGuard<'_, Access> {
+        self.access.read().unwrap()
+    }
+
+    pub fn access_mut(&self) -> RwLockWriteGuard<'_, Access> {
+        self.access.write().unwrap()
+    }
+
+    /// Moves a player into another world, see `move_client`.
+    pub fn change_world(&self, server: &mut Server<Game>, id: ClientId, world_id: WorldId) {
+        let Some(client) = server.clients.get_mut(id) else {
+            return;
+        };
+
+        self.move_client(&mut server.worlds, client, world_id);
+
+        if let Some(entity) = server.entities.get_mut(client.state.entity_id) {
+            entity.set_world(world_id);
+        }
+    }
+
+    /// Respawns a client in a world, which may be the one it is in. The chunks
+    /// it kept loaded in the world it leaves may unload, and the new world is
+    /// sent to it on the next tick. The caller moves its entity.
+    fn move_client(&self, worlds: &mut Worlds<Game>, client: &mut Client<Game>, world_id: WorldId) {
+        if client.world() != world_id {
+            if let Some(old_world) = worlds.get_mut(client.world()) {
+                self.world.release_view(old_world, client.uuid());
+            }
+
+            client.state.light_view = LightView::default();
+        }
+
+        client.respawn(world_id);
+        client.state.respawned = true;
+    }
+
+    /// The tasks started by commands, polled at the end of every tick.
+    pub fn tasks(&self) -> MutexGuard<'_, Tasks> {
+        self.tasks.lock().unwrap()
+    }
+
+    /// Sends the commands the client may run.
+    fn send_commands(&self, id: ClientId, client: &mut Client<Game>) {
+        let sender = CommandSender::player(id, client);
+        let service = self.commands();
+        let (root_id, commands) = service.get_command_defs(self, &sender);
+        client.queue_packet(&valence::protocol::packets::s2c::play::Commands {
             commands,
+            root_index: VarInt(root_id),
+        });
+    }
+
+    /// Runs a command and delivers the replies and errors to the sender.
+    fn run_command(&self, server: &mut Server<Self>, mut sender: CommandSender, command: &str) {
+        let command = self.commands().expand(command).into_owned();
+
+        // The service is released while the command runs, so that commands can
+        // add or remove commands.
+        let lookup = self.commands().lookup(&command, self, &sender);
+        let result =
+            lookup.and_then(|(run, args)| Commands::run(run, args, self, &mut sender, server));
+
+        if let Err(e) = result {
+            sender.send_message(e.render(&command));
+        }
+
+        self.deliver(server, &mut sender);
+    }
+
+    /// Delivers the messages queued for a sender.
+    pub fn deliver(&self, server: &mut Server<Self>, sender: &mut CommandSender) {
+        for reply in sender.take_replies() {
+            match sender.kind() {
+                SenderKind::Player { id, .. } => {
+                    if let Some(client) = server.clients.get_mut(*id) {
+                        client.send_message(reply);
+                    }
+                }
+                SenderKind::Console => self.print(reply),
+            }
+        }
+    }
+
+    /// Polls every task, splitting `TASK_BUDGET` between them.
+    fn poll_tasks(&self, server: &mut Server<Self>) {
+        let mut tasks = self.tasks().take();
+        if tasks.is_empty() {
+            return;
+        }
+
+        let budget = TASK_BUDGET / tasks.len() as u32;
+
+        tasks.retain_mut(|task| {
+            let done = task.poll(self, server, budget);
+            self.deliver(server, &mut task.sender);
+            !done
+        });
+
+        self.tasks().restore(tasks);
+    }
+
+    /// Prints a line on the console.
+    fn print(&self, line: impl std::fmt::Display) {
+        match &self.console {
+            Some(console) => console.print(line),
+            None => println!("{line}"),
+        }
+    }
+
+    /// Returns `true` if the client holds the wand and may select with it.
+    fn holds_wand(&self, id: ClientId, client: &Client<Game>) -> bool {
+        client
+            .state
+            .held_item()
+            .is_some_and(|item| item.item == edit::WAND)
+            && self.has_permission(
+                &CommandSender::player(id, client),
+                Some("piquant.command.pos"),
+                2,
+            )
+    }
+
+    /// Stops players from walking out of the world border and hurts players
+    /// that are outside of it, usually because it shrank.
+    fn enforce_border(&self, world: &MCWorld<Game>, client: &mut Client<Game>) {
+        let border = world.state.border();
+        let position = client.position();
+        let last = client.state.last_position;
+
+        if !border.contains(position.x, position.z) {
+            if border.contains(last.x, last.z) {
+                client.teleport(border.clamp(position), client.yaw(), client.pitch());
+            } else if world.state.time().game_time() % 20 == 0
+                && matches!(client.game_mode(), GameMode::Survival | GameMode::Adventure)
+            {
+                let distance = border.distance_outside(position.x, position.z);
+                let beyond = distance - border.damage_buffer;
+
+                if beyond > 0.0 && border.damage_per_block > 0.0 && client.state.health > 0.0 {
+                    let damage = (beyond * border.damage_per_block).floor().max(1.0) as f32;
+
+                    client.state.health = (client.state.health - damage).max(0.0);
+                    client.set_health_and_food(client.state.health, 20, 5.0);
+
+                    if client.state.health <= 0.0 {
+                        let message =
+                            format!("{} left the confines of this world", client.username());
+                        client.kill(None, message);
+                    }
+                }
+            }
+        }
+
+        client.state.last_position = client.position();
+    }
+}
+
+/// Sends the time, weather and border of the world a client is in, and where
+/// its compass points.
+fn sync_world(world_id: WorldId, world: &MCWorld<Game>, client: &mut Client<Game>) {
+    world.state.time().sync(client);
+    world.state.weather().sync(client);
+    world.state.border().sync(client);
+
+    match client.state.spawn_point {
+        Some(point) if point.world == world_id => {
+            client.set_spawn_position(point.position, point.yaw)
+        }
+        _ => {
+            if let Some(spawn) = world.state.spawn {
+                client.set_spawn_position(BlockPos::at(spawn.into_array()), 0.0);
+            }
+        }
+    }
+}
+
+/// Tells the client which block is at a position, undoing what it predicted.
+fn resend_block(world: &MCWorld<Game>, client: &mut Client<Game>, position: BlockPos) {
+    if let Some(block) = world.chunks.block_state(position) {
+        client.queue_packet(&BlockUpdate {
+            position,
+            block_id: VarInt(block.to_raw() as i32),
+        });
+    }
+}
+
+impl CommandContext for Game {
+    type Game = Game;
+    type Sender = CommandSender;
+    type Server = Server<Game>;
+}
+
+impl PermissionCheck<CommandSender> for Game {
+    fn has_permission(&self, sender: &CommandSender, node: Option<&str>, op_level: i32) -> bool {
+        match sender.kind() {
+            SenderKind::Player { uuid, name, .. } => {
+                self.permissions().allows(*uuid, name, node, op_level)
+            }
+            SenderKind::Console => true,
         }
     }
 }
@@ -71,27 +355,39 @@ impl Config for Game {
         match world_state.read_all() {
             Ok(_) => {}
             Err(e) => {
-                println!("Error reading region: {}", e);
+                self.print(format!("Error reading region: {e}"));
                 std::process::exit(1);
             }
         }
 
-        world_state.read_spawnpoint().unwrap();
+        world_state.read_level().unwrap();
 
         let (_, world) = server.worlds.insert(DimensionId::default(), world_state);
 
+        let border_config = &self.config.world.border;
+        let mut border = WorldBorder::new(
+            border_config.center_x,
+            border_config.center_z,
+            border_config.diameter,
+        );
+        border.damage_per_block = border_config.damage_per_block;
+        border.damage_buffer = border_config.damage_buffer;
+        border.set_warning_blocks(border_config.warning_blocks);
+        border.set_warning_time(border_config.warning_time);
+        *world.state.border_mut() = border;
+
         let mut player_spawn_point = Vec3::new(
             self.config.world.spawn.x as f64 + 0.5,
             0.0,
             self.config.world.spawn.z as f64 + 0.5,
         );
 
-        // generate spawn area
-        self.world.queue(
-            world,
-            player_spawn_point,
-            self.config.world.view_distance,
-            true,
+        // keep the spawn area loaded, as far as players joining there see
+        world.state.tickets_mut().add(
+            TicketKind::Spawn,
+            ChunkPos::at(player_spawn_point.x, player_spawn_point.z),
+            self.config.world.view_distance + VIEW_PADDING,
+            None,
         );
 
         self.world.update(world); // some kind of "progress" reporter would be nice
@@ -105,9 +401,35 @@ impl Config for Game {
 
             world.state.spawn = Some(player_spawn_point);
             world.state.seed = Some(self.world.seed());
+
+            if let Err(e) = world.state.write_level() {
+                self.print(format!("Error writing level.dat: {e}"));
+            }
+        }
+    }
+
+    /// Turns away banned players and, while the whitelist is on, players
+    /// that are not on it, before they take a slot.
+    async fn login(&self, _shared: &SharedServer<Self>, ncd: &NewClientData) -> Result<(), Text> {
+        let mut access = self.access_mut();
+        access.remember(ncd.uuid, ncd.username.as_str());
+
+        if let Some(ban) = access.player_ban(ncd.uuid) {
+            return Err(ban.message("You are banned from this server."));
         }
 
-        dbg!(world.state.spawn);
+        if access.whitelist_enabled
+            && !access.is_whitelisted(ncd.uuid)
+            && self.permissions().op_level(ncd.uuid) == 0
+        {
+            return Err("You are not white-listed on this server!".into());
+        }
+
+        if let Some(ban) = access.ip_ban(ncd.ip) {
+            return Err(ban.message("Your IP address is banned from this server."));
+        }
+
+        Ok(())
     }
 
     async fn server_list_ping(
@@ -130,9 +452,58 @@ impl Config for Game {
     }
 
     fn update(&self, server: &mut Server<Self>) {
-        let (world_id, world) = server.worlds.iter_mut().next().unwrap();
+        // Players join and respawn without a spawn point in the first world.
+        let main_world = server.worlds.iter().next().unwrap().0;
+
+        let players: HashMap<_, _> = server
+            .clients
+            .iter()
+            .map(|(_, client)| (client.uuid(), (client.username(), client.game_mode())))
+            .collect();
+
+        for (world_id, world) in server.worlds.iter_mut() {
+            world.state.entities_mut().rebuild(
+                server
+                    .entities
+                    .iter()
+                    .filter(|(_, entity)| entity.world() == world_id)
+                    .map(|(id, entity)| {
+                        let player = players.get(&entity.uuid());
+                        let kind = entity.kind().translation_key();
+
+                        let target = EntityTarget {
+                            id,
+                            uuid: entity.uuid(),
+                            kind: kind.rsplit('.').next().unwrap_or(kind).to_string(),
+                            position: entity.position().into_array(),
+                            name: player.map(|(name, _)| name.to_string()),
+                            game_mode: player.map(|(_, game_mode)| *game_mode),
+                            tags: Default::default(),
+                        };
+
+                        (target, entity.hitbox())
+                    }),
+            );
+        }
+
+        let player_names: Vec<_> = server
+            .clients
+            .iter()
+            .map(|(_, client)| client.username().to_string())
+            .collect();
+        let world_names = [self.config.world.name.clone()];
+
+        if self.permissions_changed.swap(false, Ordering::SeqCst) {
+            for (id, client) in server.clients.iter_mut() {
+                self.send_commands(id, client);
+            }
+        }
 
-        server.clients.retain(|_id, client| {
+        // Commands need the whole server, so they run after the clients are updated.
+        let mut commands = Vec::new();
+        let mut suggestion_requests = Vec::new();
+
+        server.clients.retain(|id, client| {
             if client.created_this_tick() {
                 if self
                     .player_count
@@ -145,6 +516,8 @@ impl Config for Game {
                     return false;
                 }
 
+                let world = &server.worlds[main_world];
+
                 if world.state.spawn.is_none() {
                     client.disconnect(
                         "Calm your tits, the server is still loading...".color(Color::RED),
@@ -157,7 +530,7 @@ impl Config for Game {
                     .insert_with_uuid(EntityKind::Player, client.uuid(), ())
                 {
                     Some((id, entity)) => {
-                        entity.set_world(world_id);
+                        entity.set_world(main_world);
                         client.state.entity_id = id
                     }
                     None => {
@@ -171,22 +544,21 @@ impl Config for Game {
 
                 let spawn = world.state.spawn.as_ref().unwrap();
 
-                client.respawn(world_id);
+                client.respawn(main_world);
                 client.set_flat(true);
 
                 // client.queue_packet(&valence::protocol::packets::s2c::login::)
 
-                let (root_id, commands) = self.commands.get_command_defs();
-                client.queue_packet(&valence::protocol::packets::s2c::play::Commands {
-                    commands,
-                    root_index: VarInt(root_id),
-                });
+                self.send_commands(id, client);
 
                 // make a string slice from self.gamemode
                 let gamemode = self.config.gameplay.gamemode.clone();
                 client.set_game_mode(gamemode.into());
 
                 client.teleport([spawn.x, spawn.y, spawn.z], 0.0, 0.0);
+                sync_world(main_world, world, client);
+
+                client.state.last_position = client.position();
                 client.set_player_list(server.state.player_lists.clone());
 
                 // client.player_mut().
@@ -205,6 +577,22 @@ impl Config for Game {
             }
             let player = &mut server.entities[client.state.entity_id];
 
+            // Light has to be sent before the events move the client, see `send_light`.
+            if !client.created_this_tick() {
+                let world_id = client.world();
+                let world = &server.worlds[world_id];
+
+                let mut light_view = client.state.light_view;
+                piquant_world::send_light(world_id, world, client, &mut light_view);
+                client.state.light_view = light_view;
+
+                // The client loaded the world it respawned in during the last
+                // tick, which reset what it knew about the world.
+                if std::mem::take(&mut client.state.respawned) {
+                    sync_world(world_id, world, client);
+                }
+            }
+
             while let Some(event) = client.next_event() {
                 match event {
                     ClientEvent::PlayerSession { .. } => {}
@@ -215,10 +603,64 @@ impl Config for Game {
                             timestamp,
                         );
                     }
+                    ClientEvent::CommandSuggestionsRequest {
+                        transaction_id,
+                        text,
+                    } => {
+                        suggestion_requests.push((id, transaction_id, text));
+                    }
                     ClientEvent::ChatCommand { command, .. } => {
-                        if let Err(e) = self.commands.execute(&command, self, client, world) {
-                            client.send_message(format!("Error: {}", e).color(Color::RED));
+                        commands.push((CommandSender::player(id, client), command.to_string()));
+                    }
+                    ClientEvent::SetHeldItem { slot } => {
+                        client.state.held_item_slot = slot;
+                    }
+                    ClientEvent::SetCreativeModeSlot {
+                        slot,
+                        ref clicked_item,
+                    } => {
+                        client
+                            .state
+                            .set_creative_mode_slot(slot, clicked_item.clone());
+                    }
+                    ClientEvent::StartDigging { position, .. } if self.holds_wand(id, client) => {
+                        let message = Selection::select(client, Corner::First, position);
+                        client.send_message(message);
+                        resend_block(&server.worlds[client.world()], client, position);
+                    }
+                    ClientEvent::UseItemOnBlock { position, .. } if self.holds_wand(id, client) => {
+                        let message = Selection::select(client, Corner::Second, position);
+                        client.send_message(message);
+                        resend_block(&server.worlds[client.world()], client, position);
+                    }
+                    ClientEvent::UseItemOnBlock { position, face, .. } => {
+                        let world = &server.worlds[client.world()];
+                        let target = position.get_in_direction(face);
+                        let (x, z) = (target.x as f64 + 0.5, target.z as f64 + 0.5);
+
+                        // Undo the block the client placed outside the border.
+                        if !world.state.border().contains(x, z) {
+                            resend_block(world, client, target);
                         }
+
+                        event.handle_default(client, player);
+                    }
+                    ClientEvent::PerformRespawn => {
+                        let (world_id, spawn, yaw) = match client.state.spawn_point {
+                            Some(point) if server.worlds.get(point.world).is_some() => {
+                                (point.world, point.center(), point.yaw)
+                            }
+                            _ => {
+                                let spawn = server.worlds[main_world].state.spawn;
+                                (main_world, spawn.unwrap_or_default(), 0.0)
+                            }
+                        };
+
+                        client.state.health = MAX_HEALTH;
+                        client.set_health_and_food(MAX_HEALTH, 20, 5.0);
+                        self.move_client(&mut server.worlds, client, world_id);
+                        player.set_world(world_id);
+                        client.teleport([spawn.x, spawn.y, spawn.z], yaw, 0.0);
                     }
                     _ => event.handle_default(client, player),
                 }
@@ -229,12 +671,17 @@ impl Config for Game {
 
             let view_distance = std::cmp::min(client_dist, server_dist);
 
+            let world = &mut server.worlds[client.world()];
+            self.enforce_border(world, client);
+
             let p = client.position();
 
-            self.world.queue(world, p, view_distance, false);
+            self.world
+                .update_view(world, client.uuid(), p, view_distance);
 
             if client.is_disconnected() {
-                println!("{} disconnected", client.username());
+                self.print(format!("{} disconnected", client.username()));
+                self.world.release_view(world, client.uuid());
                 self.player_count.fetch_sub(1, Ordering::SeqCst);
                 if let Some(id) = &server.state.player_lists {
                     server.player_lists[id].remove(client.uuid());
@@ -247,12 +694,96 @@ impl Config for Game {
             true
         });
 
+        for (world_id, world) in server.worlds.iter_mut() {
+            let time_changed = world.state.time_mut().take_changed();
+            let weather_changes = world.state.weather_mut().take_changes();
+            let border_changes = world.state.border_mut().take_changes();
+
+            if !time_changed && weather_changes.is_empty() && border_changes.is_empty() {
+                continue;
+            }
+
+            for (_, client) in server.clients.iter_mut() {
+                // Clients that respawned get the whole world next tick.
+                if client.world() != world_id || client.state.respawned {
+                    continue;
+                }
+
+                if time_changed {
+                    world.state.time().sync(client);
+                }
+
+                weather_changes.sync(client);
+                border_changes.sync(world.state.border(), client);
+            }
+        }
+
         while let Some(message) = server.state.message_queue.pop_front() {
             server.clients.iter_mut().for_each(|(_id, client)| {
                 client.send_message(message.clone());
             });
         }
 
-        self.world.update(world);
+        for (_, world) in server.worlds.iter_mut() {
+            self.world.update(world);
+        }
+
+        if let Some(console) = &self.console {
+            commands.extend(
+                console
+                    .lines()
+                    .into_iter()
+                    .map(|line| (CommandSender::console(), line)),
+            );
+        }
+
+        for (sender, command) in commands {
+            self.run_command(server, sender, &command);
+        }
+
+        self.poll_tasks(server);
+
+        let changes = self.commands_mut().take_changes();
+        if !changes.is_empty() {
+            for (id, client) in server.clients.iter_mut() {
+                let sender = CommandSender::player(id, client);
+
+                if changes.iter().any(|change| change.affects(self, &sender)) {
+                    self.send_commands(id, client);
+                }
+            }
+        }
+
+        for (id, transaction_id, text) in suggestion_requests {
+            let Some(client) = server.clients.get(id) else {
+                continue;
+            };
+
+            let sender = CommandSender::player(id, client);
+            let context = SuggestionContext {
+                game: self,
+                client: &sender,
+                world: &*server,
+                player_names: &player_names,
+                world_names: &world_names,
+            };
+
+            let response = self.commands().suggest(transaction_id, &text, &context);
+            if let Some(client) = server.clients.get_mut(id) {
+                client.queue_packet(&response);
+            }
+        }
+
+        if self.stopping.load(Ordering::SeqCst) {
+            // Changed chunks are otherwise only saved once they unload.
+            for (_, world) in server.worlds.iter_mut() {
+                let saved = self.world.save_all(world);
+                self.print(format!("Saved {saved} chunk(s)"));
+            }
+
+            server
+                .shared
+                .shutdown(Ok::<(), Box<dyn std::error::Error + Send + Sync>>(()));
+        }
     }
 }
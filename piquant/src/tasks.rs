@@ -0,0 +1,159 @@
+use std::{
+    error::Error,
+    task::Poll,
+    time::{Duration, Instant},
+};
+
+use valence::{
+    prelude::{ClientId, Color, Server},
+    protocol::TextFormat,
+};
+
+use crate::{sender::CommandSender, server::Game};
+
+/// How long all tasks together may run each tick.
+pub const TASK_BUDGET: Duration = Duration::from_millis(10);
+
+/// How often a task tells its sender how far it is.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+pub type TaskId = u32;
+
+/// Work started by a command that takes longer than a tick, like saving or
+/// filling a large region. The server polls tasks at the end of every tick
+/// until they are done or cancelled with `/cancel`.
+pub trait Task: Send {
+    /// What the task does, e.g. `Saving the world`.
+    fn name(&self) -> &str;
+
+    /// Works until `ctx.should_yield()` and returns `Poll::Ready` once the
+    /// task is done. Messages sent to `ctx.sender` are delivered after the
+    /// poll.
+    fn poll(&mut self, ctx: &mut TaskContext) -> Result<Poll<()>, Box<dyn Error>>;
+
+    /// How much of the task is done, from 0 to 1.
+    fn progress(&self) -> Option<f32> {
+        None
+    }
+
+    /// The player whose history the task records its edit in.
+    fn editor(&self) -> Option<ClientId> {
+        None
+    }
+}
+
+/// What a task can use while it is polled.
+pub struct TaskContext<'a> {
+    pub game: &'a Game,
+    pub server: &'a mut Server<Game>,
+    /// The sender of the command that started the task.
+    pub sender: &'a mut CommandSender,
+    deadline: Instant,
+}
+
+impl TaskContext<'_> {
+    /// Returns `true` once the task used up its time for this tick.
+    pub fn should_yield(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+}
+
+pub struct RunningTask {
+    pub id: TaskId,
+    pub sender: CommandSender,
+    pub task: Box<dyn Task>,
+    last_report: Instant,
+}
+
+impl RunningTask {
+    /// Polls the task for at most `budget` and reports its progress to the
+    /// sender now and then. Returns `true` once the task is done or failed.
+    pub fn poll(&mut self, game: &Game, server: &mut Server<Game>, budget: Duration) -> bool {
+        let mut ctx = TaskContext {
+            game,
+            server,
+            sender: &mut self.sender,
+            deadline: Instant::now() + budget,
+        };
+
+        match self.task.poll(&mut ctx) {
+            Ok(Poll::Ready(())) => true,
+            Ok(Poll::Pending) => {
+                if self.last_report.elapsed() >= PROGRESS_INTERVAL {
+                    self.last_report = Instant::now();
+                    self.report_progress();
+                }
+
+                false
+            }
+            Err(e) => {
+                self.sender
+                    .send_message(format!("{} failed: {e}", self.task.name()).color(Color::RED));
+
+                true
+            }
+        }
+    }
+
+    fn report_progress(&mut self) {
+        let progress = match self.task.progress() {
+            Some(progress) => format!("{:.0}% done", progress.clamp(0.0, 1.0) * 100.0),
+            None => "still running".to_string(),
+        };
+
+        let message = format!("{}: {progress} ", self.task.name()).color(Color::GRAY)
+            + format!("[/cancel {}]", self.id)
+                .color(Color::DARK_GRAY)
+                .on_click_suggest_command(format!("/cancel {}", self.id));
+
+        self.sender.send_message(message);
+    }
+}
+
+/// The tasks that are running.
+#[derive(Default)]
+pub struct Tasks {
+    next_id: TaskId,
+    running: Vec<RunningTask>,
+}
+
+impl Tasks {
+    /// Starts polling a task on the next tick, reporting to a copy of
+    /// `sender`.
+    pub fn spawn(&mut self, sender: &CommandSender, task: impl Task + 'static) -> TaskId {
+        self.next_id += 1;
+
+        let mut sender = sender.clone();
+        sender.take_replies();
+
+        self.running.push(RunningTask {
+            id: self.next_id,
+            sender,
+            task: Box::new(task),
+            last_report: Instant::now(),
+        });
+
+        self.next_id
+    }
+
+    /// Stops a task without polling it again.
+    pub fn cancel(&mut self, id: TaskId) -> Option<RunningTask> {
+        let index = self.running.iter().position(|task| task.id == id)?;
+        Some(self.running.remove(index))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RunningTask> {
+        self.running.iter()
+    }
+
+    /// Takes the tasks out to poll them, tasks spawned while they are polled
+    /// are kept.
+    pub fn take(&mut self) -> Vec<RunningTask> {
+        std::mem::take(&mut self.running)
+    }
+
+    /// Puts polled tasks back in front of the ones spawned meanwhile.
+    pub fn restore(&mut self, tasks: Vec<RunningTask>) {
+        self.running.splice(0..0, tasks);
+    }
+}